@@ -1,9 +1,11 @@
   ;; (File Descriptor, *iovs, iovs_len, nwritten) -> Returns number of bytes written
   (import "wasi_unstable" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
 
-  ;; first 40 bytes are reserved for printer
+  ;; first 40 bytes are reserved for printer, followed by stdlib messages. Grown by '$alloc'
   (memory 1)
   (export "memory" (memory 0))
+  (data (i32.const 40) "list index out of bounds, index ")
+  (data (i32.const 72) "pop from an empty list")
 
   (func $print32 (param $x i32)
     (local $i i32)
@@ -90,12 +92,23 @@
     (local $i i32)
     (local $j i32)
     (local $k i64)
+    (local $neg i32)
+
+    ;; Write sign & take magnitude. Digits are then extracted as unsigned, which also holds for
+    ;; the minimum value.
+    (local.set $neg (i64.lt_s (local.get $x) (i64.const 0)))
+    (if (local.get $neg)
+      (then
+        (i32.store8 (i32.const 8) (i32.const 45))
+        (local.set $x (i64.sub (i64.const 0) (local.get $x)))
+      )
+    )
 
     ;; Clone into K
     (local.set $k (local.get $x))
 
-    ;; Init index
-    (local.set $i (i32.const 7))
+    ;; Init index. Shifted right by one if a sign was written.
+    (local.set $i (i32.add (i32.const 7) (local.get $neg)))
 
     ;; Count Size. Incrementally decrease power of K.
     (loop $count 
@@ -171,6 +184,163 @@
     (drop)
   )
 
+  ;; Writes the decimal digits of unsigned $x at $at. Returns the address past the last digit.
+  (func $write_u64 (param $x i64) (param $at i32) (result i32)
+    (local $k i64)
+    (local $end i32)
+
+    ;; Count digits
+    (local.set $k (local.get $x))
+    (local.set $end (local.get $at))
+    (loop $count
+      (local.set $end (i32.add (local.get $end) (i32.const 1)))
+      (local.set $k (i64.div_u (local.get $k) (i64.const 10)))
+      (br_if $count (i64.ne (local.get $k) (i64.const 0)))
+    )
+
+    ;; Write digits, least significant first
+    (local.set $at (local.get $end))
+    (loop $assign
+      (local.set $at (i32.sub (local.get $at) (i32.const 1)))
+      (i64.store8
+        (local.get $at)
+        (i64.add (i64.const 48) (i64.rem_u (local.get $x) (i64.const 10)))
+      )
+      (local.set $x (i64.div_u (local.get $x) (i64.const 10)))
+      (br_if $assign (i64.ne (local.get $x) (i64.const 0)))
+    )
+    (local.get $end)
+  )
+
+  ;;@signature $print_f64 : void (f64)
+  ;; Prints up to 6 decimal places, trailing zeros trimmed. Followed by a newline.
+  ;; Magnitudes of 2^64 or more are printed in exponent form, e.g. '1.5e20'.
+  (func $print_f64 (param $x f64)
+    (local $at i32)
+    (local $int i64)
+    (local $frac i64)
+    (local $i i32)
+    (local $exp i64)
+
+    (local.set $at (i32.const 8))
+
+    (block $done
+      ;; NaN
+      (if (f64.ne (local.get $x) (local.get $x))
+        (then
+          (i32.store (local.get $at) (i32.const 0x4e614e))
+          (local.set $at (i32.add (local.get $at) (i32.const 3)))
+          (br $done)
+        )
+      )
+
+      ;; Write sign & take magnitude
+      (if (f64.lt (local.get $x) (f64.const 0))
+        (then
+          (i32.store8 (local.get $at) (i32.const 45))
+          (local.set $at (i32.add (local.get $at) (i32.const 1)))
+          (local.set $x (f64.neg (local.get $x)))
+        )
+      )
+
+      ;; Infinity
+      (if (f64.eq (local.get $x) (f64.const inf))
+        (then
+          (i32.store (local.get $at) (i32.const 0x666e69))
+          (local.set $at (i32.add (local.get $at) (i32.const 3)))
+          (br $done)
+        )
+      )
+
+      ;; Too large for the integer part. Scale into [1, 10), counting the exponent
+      (if (f64.ge (local.get $x) (f64.const 0x1p64))
+        (then
+          (loop $scale
+            (local.set $x (f64.div (local.get $x) (f64.const 10)))
+            (local.set $exp (i64.add (local.get $exp) (i64.const 1)))
+            (br_if $scale (f64.ge (local.get $x) (f64.const 10)))
+          )
+        )
+      )
+
+      ;; Split into integer & rounded fractional parts. Beyond 2^53 there are no fractions.
+      (local.set $int (i64.trunc_sat_f64_u (local.get $x)))
+      (if (f64.lt (local.get $x) (f64.const 0x1p53))
+        (then
+          (local.set $frac (i64.trunc_sat_f64_u (f64.nearest (f64.mul
+            (f64.sub (local.get $x) (f64.convert_i64_u (local.get $int)))
+            (f64.const 1e6)
+          ))))
+        )
+      )
+      (if (i64.ge_u (local.get $frac) (i64.const 1000000))
+        (then
+          (local.set $int (i64.add (local.get $int) (i64.const 1)))
+          (local.set $frac (i64.sub (local.get $frac) (i64.const 1000000)))
+        )
+      )
+      ;; Rounding may carry the mantissa out of [1, 10)
+      (if (i32.and (i64.ne (local.get $exp) (i64.const 0)) (i64.eq (local.get $int) (i64.const 10)))
+        (then
+          (local.set $int (i64.const 1))
+          (local.set $exp (i64.add (local.get $exp) (i64.const 1)))
+        )
+      )
+
+      ;; Integer part & dot
+      (local.set $at (call $write_u64 (local.get $int) (local.get $at)))
+      (i32.store8 (local.get $at) (i32.const 46))
+      (local.set $at (i32.add (local.get $at) (i32.const 1)))
+
+      ;; Fractional part, zero padded to 6 digits
+      (local.set $i (i32.const 6))
+      (loop $digits
+        (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+        (i64.store8
+          (i32.add (local.get $at) (local.get $i))
+          (i64.add (i64.const 48) (i64.rem_u (local.get $frac) (i64.const 10)))
+        )
+        (local.set $frac (i64.div_u (local.get $frac) (i64.const 10)))
+        (br_if $digits (i32.gt_u (local.get $i) (i32.const 0)))
+      )
+      (local.set $at (i32.add (local.get $at) (i32.const 6)))
+
+      ;; Trim trailing zeros, keeping at least one digit
+      (block $trimmed
+        (loop $trim
+          (br_if $trimmed (i32.ne (i32.load8_u (i32.sub (local.get $at) (i32.const 1))) (i32.const 48)))
+          (br_if $trimmed (i32.eq (i32.load8_u (i32.sub (local.get $at) (i32.const 2))) (i32.const 46)))
+          (local.set $at (i32.sub (local.get $at) (i32.const 1)))
+          (br $trim)
+        )
+      )
+
+      ;; Exponent
+      (if (i64.ne (local.get $exp) (i64.const 0))
+        (then
+          (i32.store8 (local.get $at) (i32.const 101))
+          (local.set $at (call $write_u64 (local.get $exp) (i32.add (local.get $at) (i32.const 1))))
+        )
+      )
+    )
+
+    ;; Write newline
+    (i32.store8 (local.get $at) (i32.const 10))
+    (local.set $at (i32.add (local.get $at) (i32.const 1)))
+
+    ;; Write IOV
+    (i32.store (i32.const 0) (i32.const 8))
+    (i32.store (i32.const 4) (i32.sub (local.get $at) (i32.const 8)))
+
+    (call $fd_write 
+      (i32.const 1)
+      (i32.const 0)
+      (i32.const 1)
+      (i32.const 36)
+    )
+    (drop)
+  )
+
   (func $str_len (param $str i64) (result i32)
     (local.get $str)
     (i64.const 32)
@@ -183,53 +353,313 @@
     (i32.wrap_i64)
   )
 
-  ;;@signature $print : void (str)
-  (func $print (param $str i64) 
+  ;; Packs a pointer & a length into a string.
+  (func $str_new (param $ptr i32) (param $len i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $len)) (i64.const 32))
+      (i64.extend_i32_u (local.get $ptr))
+    )
+  )
+
+  ;; Bump allocator. Returns a pointer to $size fresh bytes, 8-byte aligned. Memory is never freed.
+  ;; '$heap_ptr' is emitted by the compiler, past the static data.
+  (func $alloc (param $size i32) (result i32)
+    (local $ptr i32)
+    (local $end i32)
+    (local.set $ptr (global.get $heap_ptr))
+    (local.set $end (i32.add (local.get $ptr) (local.get $size)))
+    (global.set $heap_ptr (i32.and (i32.add (local.get $end) (i32.const 7)) (i32.const -8)))
+
+    ;; Grow memory by the missing pages
+    (if (i32.gt_u (local.get $end) (i32.mul (memory.size) (i32.const 65536)))
+      (then
+        (i32.sub (local.get $end) (i32.mul (memory.size) (i32.const 65536)))
+        (i32.add (i32.const 65535))
+        (i32.div_u (i32.const 65536))
+        (memory.grow)
+        (if (i32.eq (i32.const -1))
+          (then (unreachable))
+        )
+      )
+    )
+    (local.get $ptr)
+  )
+
+  ;; Grows the allocation at $ptr from $old to $new bytes. In place if it is the last allocation,
+  ;; otherwise moved into a fresh one.
+  (func $realloc (param $ptr i32) (param $old i32) (param $new i32) (result i32)
+    (local $moved i32)
+    (if (i32.eq (global.get $heap_ptr) (i32.and (i32.add (i32.add (local.get $ptr) (local.get $old)) (i32.const 7)) (i32.const -8)))
+      (then
+        (drop (call $alloc (i32.sub (i32.add (local.get $ptr) (local.get $new)) (global.get $heap_ptr))))
+        (return (local.get $ptr))
+      )
+    )
+    (local.set $moved (call $alloc (local.get $new)))
+    (memory.copy (local.get $moved) (local.get $ptr) (local.get $old))
+    (local.get $moved)
+  )
+
+  ;; Concatenates two strings into a fresh allocation.
+  (func $str_concat (param $a i64) (param $b i64) (result i64)
+    (local $a_len i32)
+    (local $ptr i32)
+    (local.set $a_len (call $str_len (local.get $a)))
+    (local.set $ptr (call $alloc (i32.add (local.get $a_len) (call $str_len (local.get $b)))))
+
+    (memory.copy (local.get $ptr) (call $str_ptr (local.get $a)) (local.get $a_len))
+    (memory.copy
+      (i32.add (local.get $ptr) (local.get $a_len))
+      (call $str_ptr (local.get $b))
+      (call $str_len (local.get $b))
+    )
+    (call $str_new
+      (local.get $ptr)
+      (i32.add (local.get $a_len) (call $str_len (local.get $b)))
+    )
+  )
+
+  ;; Byte-wise string equality. Returns 1 if equal, 0 otherwise.
+  (func $str_eq (param $a i64) (param $b i64) (result i32)
+    (i32.ne (call $str_len (local.get $a)) (call $str_len (local.get $b)))
+    (if (then (return (i32.const 0))))
+    (i32.eqz (call $str_cmp (local.get $a) (local.get $b)))
+  )
+
+  ;; Lexicographic string comparison, by unsigned bytes. Returns -1, 0 or 1.
+  (func $str_cmp (param $a i64) (param $b i64) (result i32)
+    (local $i i32)
+    (local $n i32)
+    (local $x i32)
+    (local $y i32)
+
+    ;; Compare up to the shorter length
+    (local.set $n (call $str_len (local.get $a)))
+    (if (i32.lt_u (call $str_len (local.get $b)) (local.get $n))
+      (then (local.set $n (call $str_len (local.get $b))))
+    )
+
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $n)))
+        (local.set $x (i32.load8_u (i32.add (call $str_ptr (local.get $a)) (local.get $i))))
+        (local.set $y (i32.load8_u (i32.add (call $str_ptr (local.get $b)) (local.get $i))))
+        (if (i32.ne (local.get $x) (local.get $y))
+          (then (return (i32.sub (i32.gt_u (local.get $x) (local.get $y)) (i32.lt_u (local.get $x) (local.get $y)))))
+        )
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)
+      )
+    )
+
+    ;; Common prefix. The shorter string is lesser
+    (i32.sub
+      (i32.gt_u (call $str_len (local.get $a)) (call $str_len (local.get $b)))
+      (i32.lt_u (call $str_len (local.get $a)) (call $str_len (local.get $b)))
+    )
+  )
+
+  ;; Arrays are laid out as an i32 length, padded to 8 bytes, followed by the elements.
+
+  ;; Allocates an array of $len elements, $size bytes each.
+  (func $arr_new (param $len i32) (param $size i32) (result i32)
+    (local $arr i32)
+    (local.set $arr (call $alloc (i32.add (i32.const 8) (i32.mul (local.get $len) (local.get $size)))))
+    (i32.store (local.get $arr) (local.get $len))
+    (local.get $arr)
+  )
+
+  ;; Address of element $i, $size bytes each. Out of bounds, panics with $msg followed by the index.
+  (func $arr_at (param $arr i32) (param $i i64) (param $size i32) (param $msg i64) (result i32)
+
+    ;; Negative indices wrap around to large unsigned ones
+    (if (i64.ge_u (local.get $i) (i64.extend_i32_u (i32.load (local.get $arr))))
+      (then (call $panic_index (local.get $msg) (local.get $i)))
+    )
+    (i32.add
+      (i32.add (local.get $arr) (i32.const 8))
+      (i32.wrap_i64 (i64.mul (local.get $i) (i64.extend_i32_u (local.get $size))))
+    )
+  )
+
+  ;; Lists are laid out as a header pointing to a separate run of elements. The header holds the
+  ;; i32 length, capacity & element pointer. Elements take 8 bytes, whatever their type.
+  ;; Generic values are passed as i64, converted by the compiler.
+
+  ;;@signature $list : list<T> ()
+  (func $list (result i32)
+    (local $l i32)
+    (local.set $l (call $alloc (i32.const 12)))
+    (i32.store (local.get $l) (i32.const 0))
+    (i32.store offset=4 (local.get $l) (i32.const 0))
+    (i32.store offset=8 (local.get $l) (i32.const 0))
+    (local.get $l)
+  )
+
+  ;;@signature $push : void (list<T>, T)
+  (func $push (param $l i32) (param $x i64)
+    (local $len i32)
+    (local $cap i32)
+    (local.set $len (i32.load (local.get $l)))
+
+    ;; Full. Double the capacity, starting at 4
+    (if (i32.eq (local.get $len) (i32.load offset=4 (local.get $l)))
+      (then
+        (local.set $cap (select (i32.shl (local.get $len) (i32.const 1)) (i32.const 4) (local.get $len)))
+        (i32.store offset=8 (local.get $l) (call $realloc
+          (i32.load offset=8 (local.get $l))
+          (i32.shl (local.get $len) (i32.const 3))
+          (i32.shl (local.get $cap) (i32.const 3))
+        ))
+        (i32.store offset=4 (local.get $l) (local.get $cap))
+      )
+    )
+    (i64.store
+      (i32.add (i32.load offset=8 (local.get $l)) (i32.shl (local.get $len) (i32.const 3)))
+      (local.get $x)
+    )
+    (i32.store (local.get $l) (i32.add (local.get $len) (i32.const 1)))
+  )
+
+  ;;@signature $pop : T (list<T>)
+  (func $pop (param $l i32) (result i64)
     (local $len i32)
+    (local.set $len (i32.load (local.get $l)))
+    (if (i32.eqz (local.get $len))
+      (then (call $panic (call $str_new (i32.const 72) (i32.const 22))))
+    )
+    (local.set $len (i32.sub (local.get $len) (i32.const 1)))
+    (i32.store (local.get $l) (local.get $len))
+    (i64.load (i32.add (i32.load offset=8 (local.get $l)) (i32.shl (local.get $len) (i32.const 3))))
+  )
 
-    ;; Write len
-    (call $str_len (local.get $str))
-    (local.set $len)
-    
-    ;; Copy into memory
-    (i32.const 8)
-    (call $str_ptr (local.get $str))
-    (local.get $len)
-    (memory.copy)
-
-    ;; Write iov
-    (i32.store (i32.const 0) (i32.const 8))
-    (i32.store (i32.const 4) (local.get $len))
+  ;;@signature $get : T (list<T>, i64)
+  (func $get (param $l i32) (param $i i64) (result i64)
+    (i64.load (call $list_at (local.get $l) (local.get $i)))
+  )
+
+  ;;@signature $set : void (list<T>, i64, T)
+  (func $set (param $l i32) (param $i i64) (param $x i64)
+    (i64.store (call $list_at (local.get $l) (local.get $i)) (local.get $x))
+  )
+
+  ;; Address of element $i. Panics if out of bounds.
+  (func $list_at (param $l i32) (param $i i64) (result i32)
+    (if (i64.ge_u (local.get $i) (i64.extend_i32_u (i32.load (local.get $l))))
+      (then (call $panic_index (call $str_new (i32.const 40) (i32.const 32)) (local.get $i)))
+    )
+    (i32.add (i32.load offset=8 (local.get $l)) (i32.wrap_i64 (i64.shl (local.get $i) (i64.const 3))))
+  )
+
+  ;; Arrays & lists both start with their i32 length.
+  ;;@signature $len : i64 ([T])
+  ;;@signature $len : i64 (list<T>)
+  (func $len (param $arr i32) (result i64)
+    (i64.extend_i32_u (i32.load (local.get $arr)))
+  )
+
+  ;; Prints a runtime error message to stderr, then traps.
+  (func $panic (param $msg i64)
+    ;; Write iovs. Message, then newline
+    (i32.store8 (i32.const 16) (i32.const 10))
+    (i32.store (i32.const 0) (call $str_ptr (local.get $msg)))
+    (i32.store (i32.const 4) (call $str_len (local.get $msg)))
+    (i32.store (i32.const 8) (i32.const 16))
+    (i32.store (i32.const 12) (i32.const 1))
 
     (call $fd_write 
-      (i32.const 1)
+      (i32.const 2)
       (i32.const 0)
-      (i32.const 1)
-      (i32.const 8)
+      (i32.const 2)
+      (i32.const 20)
     )
     (drop)
+    (unreachable)
   )
 
-  ;;@signature $println : void (str)
-  (func $println (param $str i64) 
-    (local $len i32)
+  ;; Panics with $msg followed by the index $i.
+  (func $panic_index (param $msg i64) (param $i i64)
+    (local $buf i32)
+    (local $end i32)
+
+    ;; Copy the message, then write the index. Sign & up to 20 digits
+    (local.set $buf (call $alloc (i32.add (call $str_len (local.get $msg)) (i32.const 21))))
+    (memory.copy (local.get $buf) (call $str_ptr (local.get $msg)) (call $str_len (local.get $msg)))
+    (local.set $end (i32.add (local.get $buf) (call $str_len (local.get $msg))))
+    (if (i64.lt_s (local.get $i) (i64.const 0))
+      (then
+        (i32.store8 (local.get $end) (i32.const 45))
+        (local.set $end (i32.add (local.get $end) (i32.const 1)))
+        (local.set $i (i64.sub (i64.const 0) (local.get $i)))
+      )
+    )
+    (local.set $end (call $write_u64 (local.get $i) (local.get $end)))
+    (call $panic (call $str_new (local.get $buf) (i32.sub (local.get $end) (local.get $buf))))
+  )
 
-    ;; Write len
-    (call $str_len (local.get $str))
-    (local.set $len)
-    
-    ;; Copy into memory
-    (i32.const 8)
-    (call $str_ptr (local.get $str))
-    (local.get $len)
-    (memory.copy)
+  ;; Signed division. Panics with $msg on a zero divisor.
+  (func $div_s (param $a i64) (param $b i64) (param $msg i64) (result i64)
+    (if (i64.eqz (local.get $b))
+      (then (call $panic (local.get $msg)))
+    )
+    (i64.div_s (local.get $a) (local.get $b))
+  )
 
-    ;; Write newline 
-    (i32.store8 (i32.add (local.get $len) (i32.const 8)) (i32.const 10))
+  ;; Signed remainder. Panics with $msg on a zero divisor.
+  (func $rem_s (param $a i64) (param $b i64) (param $msg i64) (result i64)
+    (if (i64.eqz (local.get $b))
+      (then (call $panic (local.get $msg)))
+    )
+    (i64.rem_s (local.get $a) (local.get $b))
+  )
 
-    ;; Write iov
-    (i32.store (i32.const 0) (i32.const 8))
-    (i32.store (i32.const 4) (i32.add (local.get $len) (i32.const 1)))
+  ;; Unsigned division. Panics with $msg on a zero divisor.
+  (func $div_u (param $a i64) (param $b i64) (param $msg i64) (result i64)
+    (if (i64.eqz (local.get $b))
+      (then (call $panic (local.get $msg)))
+    )
+    (i64.div_u (local.get $a) (local.get $b))
+  )
+
+  ;; Unsigned remainder. Panics with $msg on a zero divisor.
+  (func $rem_u (param $a i64) (param $b i64) (param $msg i64) (result i64)
+    (if (i64.eqz (local.get $b))
+      (then (call $panic (local.get $msg)))
+    )
+    (i64.rem_u (local.get $a) (local.get $b))
+  )
+
+  ;; 32 bit variants of the above.
+  (func $div_s32 (param $a i32) (param $b i32) (param $msg i64) (result i32)
+    (if (i32.eqz (local.get $b))
+      (then (call $panic (local.get $msg)))
+    )
+    (i32.div_s (local.get $a) (local.get $b))
+  )
+  (func $rem_s32 (param $a i32) (param $b i32) (param $msg i64) (result i32)
+    (if (i32.eqz (local.get $b))
+      (then (call $panic (local.get $msg)))
+    )
+    (i32.rem_s (local.get $a) (local.get $b))
+  )
+  (func $div_u32 (param $a i32) (param $b i32) (param $msg i64) (result i32)
+    (if (i32.eqz (local.get $b))
+      (then (call $panic (local.get $msg)))
+    )
+    (i32.div_u (local.get $a) (local.get $b))
+  )
+  (func $rem_u32 (param $a i32) (param $b i32) (param $msg i64) (result i32)
+    (if (i32.eqz (local.get $b))
+      (then (call $panic (local.get $msg)))
+    )
+    (i32.rem_u (local.get $a) (local.get $b))
+  )
+
+  ;;@signature $print : void (str)
+  (func $print (param $str i64) 
+    ;; Write iov. Points straight at the string's bytes
+    (i32.store (i32.const 0) (call $str_ptr (local.get $str)))
+    (i32.store (i32.const 4) (call $str_len (local.get $str)))
 
     (call $fd_write 
       (i32.const 1)
@@ -239,3 +669,21 @@
     )
     (drop)
   )
+
+  ;;@signature $println : void (str)
+  (func $println (param $str i64) 
+    ;; Write iovs. String, then newline
+    (i32.store8 (i32.const 16) (i32.const 10))
+    (i32.store (i32.const 0) (call $str_ptr (local.get $str)))
+    (i32.store (i32.const 4) (call $str_len (local.get $str)))
+    (i32.store (i32.const 8) (i32.const 16))
+    (i32.store (i32.const 12) (i32.const 1))
+
+    (call $fd_write 
+      (i32.const 1)
+      (i32.const 0)
+      (i32.const 2)
+      (i32.const 20)
+    )
+    (drop)
+  )
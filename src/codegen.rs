@@ -1,6 +1,8 @@
 mod gen;
 
 use super::*;
+use std::collections::{HashMap, HashSet};
+use semantics::FuncSig;
 
 
 #[derive(Debug, Clone)]
@@ -8,8 +10,24 @@ pub struct Prog {
     global: Glob,
     str_lit_ptr: usize,
     funcs: Vec<Func>,
+    /// Byte offsets at which each source line starts. Used for runtime error messages.
+    lines: Vec<usize>,
+    /// Declaration IDs of top-level variables. Lowered to WASM globals, visible to all functions.
+    globals: HashSet<usize>,
+    /// STD lib signatures. Generic parameters & results are passed as i64.
+    std: HashMap<String, Vec<FuncSig>>,
 }
 impl Prog {
+    /// Converts a source byte offset into a 1-indexed 'line:col' position.
+    fn line_col (&self, pos: usize) -> String {
+        let line = self.lines.partition_point(|start| *start <= pos);
+        let col = pos - self.lines[line-1] + 1;
+        format!("{line}:{col}")
+    }
+    /// Instruction setting the variable of declaration `id`.
+    fn set_instr (&self, id: usize) -> &'static str {
+        if self.globals.contains(&id) { "global.set" } else { "local.set" }
+    }
     fn add_func (&mut self, f: Func) {
         self.funcs.push(f)
     }
@@ -17,8 +35,18 @@ impl Prog {
         self.global.push(s);
     }
     fn add_str_lit (&mut self, s: &str) -> usize {
+        // Escape everything but printable ASCII. Lengths are in bytes.
+        let data = s.bytes().fold(String::new(), |mut data, b| {
+            if b.is_ascii_graphic() && b != b'"' && b != b'\\' || b == b' ' {
+                data.push(b as char);
+            } else {
+                data.push_str(&format!("\\{:02x}", b));
+            }
+            data
+        });
+
         let out = self.str_lit_ptr;
-        self.global.push(&format!("(data (i32.const {}) \"{s}\")", out));
+        self.global.push(&format!("(data (i32.const {}) \"{data}\")", out));
         self.str_lit_ptr += s.len();
 
         return out;
@@ -52,7 +80,10 @@ pub struct Func {
     sig: String,
     p: String,
     v: String,
-    t: u32
+    t: u32,
+    labels: usize,
+    loops: Vec<usize>,
+    temps: usize,
 }
 impl Func {
     fn new (sig: String) -> Self {
@@ -60,7 +91,10 @@ impl Func {
             sig,
             p: String::new(),
             v: String::new(),
-            t: 2
+            t: 2,
+            labels: 0,
+            loops: vec![],
+            temps: 0,
         }
     }
     fn push (&mut self, s: &str) {
@@ -80,6 +114,26 @@ impl Func {
     fn push_s (&mut self, s: String) {
         self.push(s.as_str()); 
     }
+    /// Enters a loop. Returns the index used to label its `$break` block and `$continue` loop.
+    fn push_loop (&mut self) -> usize {
+        self.labels += 1;
+        self.loops.push(self.labels);
+        self.labels
+    }
+    fn pop_loop (&mut self) {
+        self.loops.pop();
+    }
+    /// Label index of the innermost loop. 
+    fn loop_label (&self) -> usize {
+        *self.loops.last().expect("break/continue outside of loop. Should've been caught in type checking")
+    }
+    /// Declares a fresh local of WASM type `t`, for intermediate values. Returns its name.
+    fn temp (&mut self, t: &str) -> String {
+        self.temps += 1;
+        let name = format!("$tmp{}", self.temps);
+        self.prefix(format!("(local {name} {t})"));
+        name
+    }
     fn prefix (&mut self, s: String) {
         self.p.push_str("    ");
         self.p.push_str(s.as_str());
@@ -100,16 +154,29 @@ impl Func {
 }
 
 
-pub fn gen (nodes: Vec<LNode>) -> String {
+pub fn gen (nodes: Vec<LNode>, source: &str) -> String {
+    let lines = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i+1))
+        .collect();
+    let globals = nodes
+        .iter()
+        .filter_map(|node| match &node.data {
+            NodeData::Declaration { ident, .. } => Some(ident.id),
+            _ => None
+        })
+        .collect();
     let mut prog = Prog { 
         global: Glob::default(),
         funcs: vec![],
         str_lit_ptr: super::stdlib::RESERVED_MEM,
+        lines,
+        globals,
+        std: stdlib::signatures(),
     };
     let mut main = Func::new("func $_main".to_owned());
 
     for node in nodes {
-        node.codegen(&mut prog, &mut main);
+        node.codegen_stmt(&mut prog, &mut main);
     }
 
     to_string(prog, main)
@@ -123,6 +190,11 @@ fn to_string(prog: Prog, main: Func) -> String {
     code.push_str(&stdlib::get_lib());
 
     code.push_str(&prog.global.v);
+
+    // Heap starts past the static data
+    let heap = (prog.str_lit_ptr + 7) & !7;
+    code.push_str(&format!("  (global $heap_ptr (mut i32) (i32.const {heap}))\n"));
+
     for f in prog.funcs {
         code.push_str(&f.to_string());
     }
@@ -134,3 +206,180 @@ fn to_string(prog: Prog, main: Func) -> String {
     code
 }
 
+
+
+#[cfg(test)]
+mod test {
+    use wasmtime::*;
+
+    /// Compiles & runs the source. Returns everything it wrote, through any file descriptor.
+    fn run (source: &str) -> String {
+        let (code, _) = crate::compile(&source.to_owned()).ok().expect("compilation failed");
+
+        let engine = Engine::default();
+        let module = Module::new(&engine, code).expect("could not build module");
+        let mut store = Store::new(&engine, vec![]);
+        let mut linker = Linker::new(&engine);
+        linker.func_wrap("wasi_unstable", "fd_write", 
+            |mut caller: Caller<'_, Vec<u8>>, _fd: i32, iovs: i32, len: i32, written: i32| -> i32 {
+                let memory = caller.get_export("memory").and_then(Extern::into_memory).unwrap();
+                let mut out = vec![];
+                for i in 0..len as usize {
+                    let mut iov = [0u8; 8];
+                    memory.read(&caller, iovs as usize + 8*i, &mut iov).unwrap();
+                    let ptr = u32::from_le_bytes([iov[0], iov[1], iov[2], iov[3]]) as usize;
+                    let len = u32::from_le_bytes([iov[4], iov[5], iov[6], iov[7]]) as usize;
+                    out.extend_from_slice(&memory.data(&caller)[ptr..ptr+len]);
+                }
+                memory.write(&mut caller, written as usize, &(out.len() as u32).to_le_bytes()).unwrap();
+                caller.data_mut().extend(out);
+                0
+            }
+        ).unwrap();
+
+        let instance = linker.instantiate(&mut store, &module).expect("could not link");
+        let start = instance.get_typed_func::<(), ()>(&mut store, "_start").unwrap();
+        // Runtime errors trap once their message is written
+        let _ = start.call(&mut store, ());
+        String::from_utf8(store.into_data()).unwrap()
+    }
+
+    #[test]
+    fn while_loops () {
+        let source = "
+            let mut i = 0
+            while true {
+                i += 1
+                if i == 2 { continue }
+                if i > 4 { break }
+                print64(i)
+            }
+        ";
+        assert_eq!(run(source), "1\n3\n4\n");
+    }
+
+    #[test]
+    fn assignment () {
+        let source = "
+            let mut x = 10
+            x = x + 1 print64(x)
+            x += 4 print64(x)
+            x -= 5 print64(x)
+            x *= 3 print64(x)
+            x /= 4 print64(x)
+        ";
+        assert_eq!(run(source), "11\n15\n10\n30\n7\n");
+    }
+
+    #[test]
+    fn comparisons () {
+        // Signed for i64, unsigned for u64
+        let source = "
+            print64((0 - 1 < 1) as i64)
+            print64((2 <= 2) as i64)
+            print64((0 - 3 > 0 - 2) as i64)
+            print64((u64(0) - u64(1) > u64(1)) as i64)
+        ";
+        assert_eq!(run(source), "1\n1\n0\n1\n");
+    }
+
+    #[test]
+    fn division () {
+        assert_eq!(run("print64(7 / 2)\nprint64((0 - 7) / 2)\nprint64((0 - 7) % 2)"), "3\n-3\n-1\n");
+        assert_eq!(run("let z = 0\nprint64(1)\nprint64(10 / z)\nprint64(2)"), "1\ndivision by zero at 3:14\n");
+        assert_eq!(run("let z = 0\nprint64(10 % z)"), "division by zero at 2:14\n");
+    }
+
+    #[test]
+    fn concatenation () {
+        assert_eq!(run("let mut s = \"ab\"\ns = s + \"c\" + \"\"\nprintln(s + s)"), "abcabc\n");
+        assert_eq!(run("let mut s = \"\"\nfor i in 0..3 { s += \"x\" }\nprintln(s)"), "xxx\n");
+    }
+
+    #[test]
+    fn string_comparisons () {
+        let source = "let a = \"ab\"\nlet b = \"a\" + \"b\"\n\
+            print64((a == b) as i64)\nprint64((a != b) as i64)\nprint64((a == \"abc\") as i64)\n\
+            print64((\"ab\" < \"b\") as i64)\nprint64((\"ab\" < \"abc\") as i64)\nprint64((\"b\" > \"abc\") as i64)\n\
+            print64((\"\" >= \"a\") as i64)\nprint64((a <= b) as i64)";
+        assert_eq!(run(source), "1\n0\n0\n1\n1\n1\n0\n1\n");
+    }
+
+    #[test]
+    fn mutual_recursion () {
+        let source = "print64(even(10) as i64)\nprint64(odd(7) as i64)\nprint64(even(3) as i64)\n\
+            func even (n: i64) -> bool { if n == 0 { return true }; return odd(n - 1) }\n\
+            func odd (n: i64) -> bool { if n == 0 { return false }; return even(n - 1) }";
+        assert_eq!(run(source), "1\n1\n0\n");
+    }
+
+    #[test]
+    fn shadowing () {
+        let source = "let x = 1\n{ let x = 2; print64(x) }\n{ let x = 3.5; print_f64(x) }\n\
+            { let x = x + 10; { let x = x * 2; print64(x) } }\nprint64(x)";
+        assert_eq!(run(source), "2\n3.5\n22\n1\n");
+    }
+
+    #[test]
+    fn globals () {
+        let source = "let mut count = 0\nlet step = 5\n\
+            func inc () -> void { count += step }\nfunc total () -> i64 { return count }\n\
+            inc()\ninc()\nprint64(count)\nprint64(total())";
+        assert_eq!(run(source), "10\n10\n");
+    }
+
+    #[test]
+    fn block_values () {
+        let source = "let y = { let a = 2; a * 3 }\nprint64(y)\n\
+            let c = false\nlet z: i64 = if c { 1 } else if y > 5 { 2 } else { 3 }\nprint64(z)\n\
+            print_f64(if true { 1.5 } else { 0.5 })";
+        assert_eq!(run(source), "6\n2\n1.5\n");
+    }
+
+    #[test]
+    fn else_if_chains () {
+        let source = "
+            func sign (n: i64) -> void {
+                if n < 0 {
+                    println(\"negative\")
+                } else if n == 0 {
+                    println(\"zero\")
+                } else {
+                    println(\"positive\")
+                }
+            }
+            sign(0 - 3) sign(0) sign(5)
+        ";
+        assert_eq!(run(source), "negative\nzero\npositive\n");
+    }
+
+    #[test]
+    fn for_loops () {
+        assert_eq!(run("for i in 0..3 { print64(i) }"), "0\n1\n2\n");
+        assert_eq!(run("for i in 1..8 step 3 { print64(i) }"), "1\n4\n7\n");
+        // Negative steps count down, past the end
+        assert_eq!(run("for i in 3..0 step 0 - 2 { print64(i) }"), "3\n1\n");
+        assert_eq!(run("for i in 0..3 step 0 - 1 { print64(i) }"), "");
+        // Zero steps are reported where the step is written
+        assert_eq!(run("let z = 0\nfor i in 0..3 step z { print64(i) }"), "for loop step of zero at 2:20\n");
+    }
+
+    #[test]
+    fn print_f64 () {
+        assert_eq!(run("print_f64(2.5)\nprint_f64(0.0 - 0.125)"), "2.5\n-0.125\n");
+        // Beyond the range of u64, in exponent form
+        assert_eq!(run("print_f64(1e20)\nprint_f64(0.0 - 1.5e300)"), "1.0e20\n-1.5e300\n");
+        assert_eq!(run("print_f64(9.9999999e25)"), "1.0e26\n");
+    }
+
+    #[test]
+    fn coerced_literals () {
+        let source = "
+            func f () -> u8 { return 250 }
+            let b: list<u8> = list()
+            push(b, 5)
+            print64(get(b, 0) as i64 + f() as i64)
+        ";
+        assert_eq!(run(source), "255\n");
+    }
+}
@@ -7,8 +7,26 @@ impl LElem {
             LElem::Token(t) => t.codegen(prog, func)
         }
     }
+    /// Generates a statement. Values of expression statements are dropped.
+    fn codegen_stmt (&self, prog: &mut Prog, func: &mut Func) {
+        match self {
+            LElem::Node(n)  => n.codegen_stmt(prog, func),
+            LElem::Token(t) => {
+                t.codegen(prog, func);
+                func.push("(drop)");
+            }
+        }
+    }
 }
 impl LNode {
+    /// Generates a statement. Values of expression statements are dropped.
+    pub fn codegen_stmt (&self, prog: &mut Prog, func: &mut Func) {
+        self.codegen(prog, func);
+        if NodeT::from(&self.data).is_evaluable() && self.t != Type::Void {
+            func.push("(drop)");
+        }
+    }
+
     pub fn codegen (&self, prog: &mut Prog, func: &mut Func) {
         match &self.data {
             NodeData::Function { ident, params, r_type, block } => {
@@ -21,63 +39,225 @@ impl LNode {
                 }
 
                 // Write return type
-                match r_type {
-                    Type::Int    => func.prefix("(result i64)".to_owned()),
-                    Type::Bool   => func.prefix("(result i32)".to_owned()),
-                    Type::String => func.prefix("(result i64)".to_owned()),
-                    Type::Void   => ()
-                }               
+                if *r_type != Type::Void {
+                    func.prefix(format!("(result {})", r_type.gen()));
+                }
 
                 // Block
-                block.codegen(prog, &mut func);
+                block.codegen_stmt(prog, &mut func);
+
+                // Every path returned already. The stack is left empty, so validation needs this
+                if *r_type != Type::Void {
+                    func.push("(unreachable)");
+                }
 
                 prog.add_func(func);
             },
-            NodeData::Params{ v } => {
+            NodeData::Params{ v, .. } => {
                 for (ident, t) in v {
                     func.prefix(format!("(param ${ident} {})", t.gen()));
                 }
             },
-            NodeData::Block { v } => {
+            NodeData::Block { v } if self.t == Type::Void => {
                 for child in v {
-                    child.codegen(prog, func);
+                    child.codegen_stmt(prog, func);
+                }
+            },
+            // The trailing expression is left on the stack as the block's value
+            NodeData::Block { v } => {
+                func.push_s(format!("(block (result {})", self.t.gen()));
+                let (tail, stmts) = v.split_last().expect("valued block should have a tail");
+                for child in stmts {
+                    child.codegen_stmt(prog, func);
                 }
+                tail.codegen(prog, func);
+                func.push(")");
             },
-            NodeData::Declaration{ ident, expr } => {
+            NodeData::Declaration{ ident, expr, .. } => {
 
                 println!("{:?}", expr);
-                let expr_t = expr.t();
+                let t = expr.t().gen();
 
-                // Declare local variable
-                func.prefix(format!("(local ${ident} {})", expr_t.gen()));
+                // Declare variable. Top-level ones are globals, initialized once reached in main.
+                if prog.globals.contains(&ident.id) {
+                    prog.push_g(&format!("(global ${ident} (mut {t}) ({t}.const 0))"));
+                } else {
+                    func.prefix(format!("(local ${ident} {t})"));
+                }
 
                 // Expand Expression
-                func.push_s(format!("(local.set ${}", ident));
+                func.push_s(format!("({} ${}", prog.set_instr(ident.id), ident));
                 expr.codegen(prog, func);
                 func.push(")");
             },
-            NodeData::If{ expr, block } => {
+            NodeData::Assignment{ ident, expr } => {
+                func.push_s(format!("({} ${}", prog.set_instr(ident.id), ident));
+                expr.codegen(prog, func);
+                func.push(")");
+            },
+            NodeData::If{ expr, block, else_block } => {
 
                 expr.codegen(prog, func);
 
-                func.push("(if");
+                // Branches only keep their values when the if evaluates to one
+                let branch = |elem: &LElem, prog: &mut Prog, func: &mut Func| {
+                    if self.t == Type::Void {
+                        elem.codegen_stmt(prog, func);
+                    } else {
+                        elem.codegen(prog, func);
+                    }
+                };
+                if self.t == Type::Void {
+                    func.push("(if");
+                } else {
+                    func.push_s(format!("(if (result {})", self.t.gen()));
+                }
                 func.push("(then");
-                block.codegen(prog, func);
+                branch(block, prog, func);
                 func.push(")");
+                if let Some(else_block) = else_block {
+                    func.push("(else");
+                    branch(else_block, prog, func);
+                    func.push(")");
+                }
                 func.push(")");
             },
-            NodeData::Return { expr } => {
+            NodeData::While{ expr, block } => {
+                let l = func.push_loop();
+
+                func.push_s(format!("(block $break{l}"));
+                func.push_s(format!("(loop $continue{l}"));
+
+                // Exit once the condition fails
                 expr.codegen(prog, func);
+                func.push("(i32.eqz)");
+                func.push_s(format!("(br_if $break{l})"));
+
+                block.codegen_stmt(prog, func);
+                func.push_s(format!("(br $continue{l})"));
+                func.push(")");
+                func.push(")");
+
+                func.pop_loop();
+            },
+            NodeData::For{ ident, from, to, step, block } => {
+                let l = func.push_loop();
+                let end = format!("$for{l}@end");
+                let inc = format!("$for{l}@step");
+
+                // Declare loop variable & range locals. The range is only evaluated once.
+                func.prefix(format!("(local ${ident} i64)"));
+                func.prefix(format!("(local {end} i64)"));
+                func.prefix(format!("(local {inc} i64)"));
+
+                func.push_s(format!("(local.set ${ident}"));
+                from.codegen(prog, func);
+                func.push(")");
+                func.push_s(format!("(local.set {end}"));
+                to.codegen(prog, func);
+                func.push(")");
+                func.push_s(format!("(local.set {inc}"));
+                if let Some(step) = step {
+                    step.codegen(prog, func);
+                } else {
+                    func.push("(i64.const 1)");
+                }
+                func.push(")");
+
+                // A zero step would never exhaust the range
+                if let Some(step) = step {
+                    let msg = format!("for loop step of zero at {}", prog.line_col(step.start()));
+                    func.push_s(format!("(if (i64.eqz (local.get {inc}))"));
+                    func.push("(then (call $panic");
+                    str_lit(prog, func, &msg);
+                    func.push(")))");
+                }
+
+                func.push_s(format!("(block $break{l}"));
+                func.push_s(format!("(loop $for{l}"));
+
+                // Exit once the range is exhausted. Counts down on a negative step.
+                func.push_s(format!("(i64.ge_s (local.get ${ident}) (local.get {end}))"));
+                func.push_s(format!("(i64.le_s (local.get ${ident}) (local.get {end}))"));
+                func.push_s(format!("(i64.gt_s (local.get {inc}) (i64.const 0))"));
+                func.push("(select)");
+                func.push_s(format!("(br_if $break{l})"));
+
+                // 'continue' exits the body, onto the increment
+                func.push_s(format!("(block $continue{l}"));
+                block.codegen_stmt(prog, func);
+                func.push(")");
+
+                func.push_s(format!("(local.set ${ident} (i64.add (local.get ${ident}) (local.get {inc})))"));
+                func.push_s(format!("(br $for{l})"));
+                func.push(")");
+                func.push(")");
+
+                func.pop_loop();
+            },
+            NodeData::Break => {
+                func.push_s(format!("(br $break{})", func.loop_label()));
+            },
+            NodeData::Continue => {
+                func.push_s(format!("(br $continue{})", func.loop_label()));
+            },
+            NodeData::Return { expr } => {
+                if let Some(expr) = expr {
+                    expr.codegen(prog, func);
+                }
                 func.push("return");
             },
+            // Elements are stored into a fresh allocation, past the length.
+            NodeData::Array { v } => {
+                let Type::Array(elem_t) = &self.t else { panic!() };
+                let size = elem_t.size();
+                let (_, store) = elem_t.load_store();
+                let arr = func.temp("i32");
+
+                func.push_s(format!("(local.set {arr} (call $arr_new (i32.const {}) (i32.const {size})))", v.len()));
+                for (i, elem) in v.iter().enumerate() {
+                    func.push_s(format!("({store} offset={} (local.get {arr})", 8 + i*size));
+                    elem.codegen(prog, func);
+                    func.push(")");
+                }
+                func.push_s(format!("(local.get {arr})"));
+            },
+            NodeData::Index { expr, index } => {
+                let (load, _) = self.t.load_store();
+                func.push_s(format!("({load}"));
+                elem_addr(prog, func, expr, index);
+                func.push(")");
+            },
+            NodeData::IndexAssignment { expr, index, value } => {
+                let (_, store) = value.t().load_store();
+                func.push_s(format!("({store}"));
+                elem_addr(prog, func, expr, index);
+                value.codegen(prog, func);
+                func.push(")");
+            },
             NodeData::Invoke { ident, args } => {
+                // Generic STD lib functions take & return their 'T' values as i64
+                let arg_t: Vec<_> = args.iter().map(|arg| arg.t().clone()).collect();
+                let sig = prog.std.get(ident)
+                    .and_then(|sigs| semantics::select_overload(sigs, &arg_t))
+                    .cloned();
+                let is_generic = |i: usize| sig.as_ref().is_some_and(|(params, _)| params[i] == Type::Generic);
+                let narrow = sig.as_ref()
+                    .filter(|(_, r_type)| *r_type == Type::Generic)
+                    .and_then(|_| self.t.conversion().map(|(_, narrow)| narrow));
+
+                if let Some(narrow) = narrow { func.push(narrow); }
                 func.push_s(format!("(call ${}", ident));
 
-                for arg in args {
+                for (i, arg) in args.iter().enumerate() {
+                    let widen = if is_generic(i) { arg.t().conversion().map(|(widen, _)| widen) } else { None };
+                    if let Some(widen) = widen { func.push(widen); }
                     arg.codegen(prog, func);
+                    if widen.is_some() { func.push(")"); }
                 }
 
                 func.push(")");
+                if narrow.is_some() { func.push(")"); }
             },
             NodeData::UExpr { op, t } => {
                 match op.as_str() {
@@ -87,18 +267,99 @@ impl LNode {
                         t.codegen(prog, func);
                         func.push(")");
                     },
+                    "-" if self.t == Type::Float => {
+                        func.push("(f64.neg");
+                        t.codegen(prog, func);
+                        func.push(")");
+                    },
+                    "-" => {
+                        let t_gen = self.t.gen();
+                        func.push_s(format!("({t_gen}.sub"));
+                        func.push_s(format!("({t_gen}.const 0)"));
+                        t.codegen(prog, func);
+                        func.push(")");
+                    },
                     op @ _ => panic!("found unimplemented unary operator: {op}")
                 }
             },
+            NodeData::Cast { t, expr } => {
+                let from = expr.t();
+
+                // Change representation. Integer extension & float conversion follow the source's
+                // sign, float truncation follows the target's & saturates.
+                let sign = |t: &Type| if t.is_signed() { "s" } else { "u" };
+                let instr = match (from.gen(), t.gen()) {
+                    _ if from == t => None,
+                    (a, _) if *t == Type::Bool => Some(format!("({a}.ne")),
+                    (a, b) if a == b => None,
+                    ("i64", "i32") => Some("(i32.wrap_i64".to_owned()),
+                    ("i32", "i64") => Some(format!("(i64.extend_i32_{}", sign(from))),
+                    ("f64", b) => Some(format!("({b}.trunc_sat_f64_{}", sign(t))),
+                    (a, "f64") => Some(format!("(f64.convert_{a}_{}", sign(from))),
+                    (a, b) => panic!("found unimplemented conversion from {a} to {b}")
+                };
+
+                if *t == Type::U8 { func.push("(i32.and"); }
+                if let Some(instr) = &instr { func.push(instr); }
+                expr.codegen(prog, func);
+                if *t == Type::Bool && *from != Type::Bool { 
+                    func.push_s(format!("({}.const 0)", from.gen()));
+                }
+                if instr.is_some() { func.push(")"); }
+                if *t == Type::U8 { 
+                    func.push("(i32.const 255)");
+                    func.push(")");
+                }
+            },
             NodeData::Expr{ op, t1, t2 } => {
 
-                // Equality 
-                if matches!(op.as_str(), "==" | "!=") {
-                    func.push_s(format!(
-                        "({}.{}",
-                        t1.t().gen(),
-                        if &op[..] == "==" { "eq" } else { "ne" }
-                    ));
+                // String Equality & Comparison go through the stdlib. Compared by content.
+                if *t1.t() == Type::String && matches!(op.as_str(), "==" | "!=" | "<" | "<=" | ">" | ">=") {
+                    if op == "==" || op == "!=" {
+                        if op == "!=" { func.push("(i32.eqz"); }
+                        func.push("(call $str_eq");
+                        t1.codegen(prog, func);
+                        t2.codegen(prog, func);
+                        func.push(")");
+                        if op == "!=" { func.push(")"); }
+                    } else {
+                        let instr = match op.as_str() {
+                            "<"  => "lt_s",
+                            "<=" => "le_s",
+                            ">"  => "gt_s",
+                            ">=" => "ge_s",
+                            _ => unreachable!()
+                        };
+                        func.push_s(format!("(i32.{instr}"));
+                        func.push("(call $str_cmp");
+                        t1.codegen(prog, func);
+                        t2.codegen(prog, func);
+                        func.push(")");
+                        func.push("(i32.const 0)");
+                        func.push(")");
+                    }
+
+                    return
+                }
+
+                // Equality & Comparison. Follows the sign of the integer type.
+                if matches!(op.as_str(), "==" | "!=" | "<" | "<=" | ">" | ">=") {
+                    let instr = match op.as_str() {
+                        "==" => "eq",
+                        "!=" => "ne",
+                        "<"  => "lt",
+                        "<=" => "le",
+                        ">"  => "gt",
+                        ">=" => "ge",
+                        _ => unreachable!()
+                    };
+                    let sign = match op.as_str() {
+                        "==" | "!=" => "",
+                        _ if *t1.t() == Type::Float => "",
+                        _ if t1.t().is_signed() => "_s",
+                        _ => "_u",
+                    };
+                    func.push_s(format!("({}.{}{}", t1.t().gen(), instr, sign));
                     t1.codegen(prog, func);
                     t2.codegen(prog, func);
                     func.push(")");
@@ -106,34 +367,73 @@ impl LNode {
                     return
                 }
 
-                match self.t {
-                    Type::Int => {
+                // Division & Remainder go through the stdlib, which reports zero divisors.
+                if matches!(op.as_str(), "/" | "%") && self.t.is_int() {
+                    let msg = format!("division by zero at {}", prog.line_col(t2.start()));
+                    let sign = if self.t.is_signed() { "s" } else { "u" };
+                    let width = if self.t.gen() == "i32" { "32" } else { "" };
+                    let name = if op == "/" { "div" } else { "rem" };
+                    func.push_s(format!("(call ${name}_{sign}{width}"));
+                    t1.codegen(prog, func);
+                    t2.codegen(prog, func);
+                    str_lit(prog, func, &msg);
+                    func.push(")");
+
+                    return
+                }
+
+                match &self.t {
+                    t if t.is_int() => {
                         let a = match op.as_str() {
-                            "+" => "(i64.add",
-                            "-" => "(i64.sub",
-                            "*" => "(i64.mul",
-                            "/" => "(i64.div",
+                            "+" => "add",
+                            "-" => "sub",
+                            "*" => "mul",
                             op @ _ => panic!("found unimplemented integer operator: {op}")
                         };
+                        // u8 wraps around at 256
+                        if *t == Type::U8 { func.push("(i32.and"); }
+                        func.push_s(format!("({}.{a}", t.gen()));
+                        t1.codegen(prog, func);
+                        t2.codegen(prog, func);
+                        func.push(")");
+                        if *t == Type::U8 { 
+                            func.push("(i32.const 255)");
+                            func.push(")");
+                        }
+                    },
+                    Type::Float => {
+                        let a = match op.as_str() {
+                            "+" => "(f64.add",
+                            "-" => "(f64.sub",
+                            "*" => "(f64.mul",
+                            "/" => "(f64.div",
+                            op => panic!("found unimplemented float operator: {op}")
+                        };
                         func.push(a);
                         t1.codegen(prog, func);
                         t2.codegen(prog, func);
                         func.push(")");
                     },
                     Type::Bool => {
+                        // Booleans are always 0 or 1, so bitwise operators suffice.
                         let a = match op.as_str() {
-                            "||" => Some("(i32.or"),
-                            "&&" => Some("(i32.and"),
-                            op @ _ => panic!("found unimplemented boolean operator: {op}")
+                            "||" => "(i32.or",
+                            "&&" => "(i32.and",
+                            op => panic!("found unimplemented boolean operator: {op}")
                         };
-                        func.push("(i32.ge_u");
-                        if let Some(a) = a { func.push(a) } 
+                        func.push(a);
+                        t1.codegen(prog, func);
+                        t2.codegen(prog, func);
+                        func.push(")");
+                    },
+                    Type::String => {
+                        let a = match op.as_str() {
+                            "+" => "(call $str_concat",
+                            op => panic!("found unimplemented string operator: {op}")
+                        };
+                        func.push(a);
                         t1.codegen(prog, func);
                         t2.codegen(prog, func);
-                        if let Some(_) = a { 
-                            func.push("(i32.const 1)");
-                            func.push(")");
-                        } 
                         func.push(")");
                     },
                     _ => panic!("Expressions not implemented for type {}", self.t)
@@ -148,38 +448,91 @@ impl LToken {
     fn codegen (&self, prog: &mut Prog, func: &mut Func) {
         match &self.data {
             TokenData::INT(n) => {
-                func.push_s(format!("(i64.const {})", n));
+                // Coerced literals take on the representation of their type
+                func.push_s(format!("({}.const {})", self.t.gen(), n));
+            },
+            TokenData::FLOAT(n) => {
+                func.push_s(format!("(f64.const {})", n));
             },
             TokenData::BOOL(b) => {
                 func.push_s(format!("(i32.const {})", if *b { 1 } else { 0 }));
             },
 
-            TokenData::IDENT(ident) => {
-                func.push_s(format!("(local.get ${})", ident));
+            TokenData::IDENT(_) => {
+                let instr = if prog.globals.contains(&self.id) { "global.get" } else { "local.get" };
+                func.push_s(format!("({instr} ${})", self.symbol().unwrap()));
             }
             TokenData::STR_LIT(str) => {
-                // Make literal in linear memory
-                let ptr = prog.add_str_lit(str);
-
-                // Write string pointer representation
-                func.push(&format!("(i64.const {})", str.len()));
-                func.push("(i64.const 32)");
-                func.push("(i64.rotr)");
-                func.push(&format!("(i64.const {})", ptr));
-                func.push("(i64.add)");
+                str_lit(prog, func, str);
             }
             _ => panic!("codegen unimplemented for {}", self.data)
         }
     }
 }
 
+/// Writes the address of element `index` of array `expr`. Bounds are checked by the stdlib.
+fn elem_addr (prog: &mut Prog, func: &mut Func, expr: &LElem, index: &LElem) {
+    let Type::Array(elem_t) = expr.t() else { panic!() };
+    let msg = format!("array access out of bounds at {}, index ", prog.line_col(index.start()));
+
+    func.push("(call $arr_at");
+    expr.codegen(prog, func);
+    index.codegen(prog, func);
+    func.push_s(format!("(i32.const {})", elem_t.size()));
+    str_lit(prog, func, &msg);
+    func.push(")");
+}
+
+/// Makes a string literal in linear memory & writes its pointer representation.
+fn str_lit (prog: &mut Prog, func: &mut Func, str: &str) {
+    let ptr = prog.add_str_lit(str);
+
+    func.push(&format!("(i64.const {})", str.len()));
+    func.push("(i64.const 32)");
+    func.push("(i64.rotr)");
+    func.push(&format!("(i64.const {})", ptr));
+    func.push("(i64.add)");
+}
+
 impl Type {
     fn gen (&self) -> &str {
         match self {
             Type::Int    => "i64",
+            Type::I32    => "i32",
+            Type::U8     => "i32",
+            Type::U64    => "i64",
+            Type::Float  => "f64",
             Type::Bool   => "i32",
             Type::String => "i64",
+            Type::Array(_) => "i32",
+            Type::List(_)  => "i32",
             _ => panic!("codegen unimplemented for type {}", self)
         }
     }
+
+    /// Bytes taken as an array element.
+    fn size (&self) -> usize {
+        match self {
+            Type::U8 => 1,
+            t => if t.gen() == "i32" { 4 } else { 8 },
+        }
+    }
+
+    /// Instructions converting into & from the i64 representation of generic values. None if
+    /// already an i64.
+    fn conversion (&self) -> Option<(&'static str, &'static str)> {
+        match self.gen() {
+            "i32" => Some(("(i64.extend_i32_u", "(i32.wrap_i64")),
+            "f64" => Some(("(i64.reinterpret_f64", "(f64.reinterpret_i64")),
+            _ => None,
+        }
+    }
+
+    /// Instructions loading & storing an array element.
+    fn load_store (&self) -> (String, String) {
+        match self {
+            Type::U8 => ("i32.load8_u".to_owned(), "i32.store8".to_owned()),
+            t => (format!("{}.load", t.gen()), format!("{}.store", t.gen())),
+        }
+    }
 }
@@ -19,7 +19,9 @@ pub struct LToken {
     pub start: usize,
     pub end: usize,
     pub data: TokenData,
-    pub t: Type
+    pub t: Type,
+    /// Declaration ID of an identifier. Assigned in scope resolution, 0 otherwise.
+    pub id: usize,
 }
 impl LToken {
     pub fn from (tok: Token) -> Self {
@@ -28,8 +30,28 @@ impl LToken {
             start: tok.start,
             end: tok.end,
             data: tok.data,
+            id: 0,
         }
     }
+    /// The variable this token refers to, if it is an identifier.
+    pub fn symbol (&self) -> Option<Symbol> {
+        if let TokenData::IDENT(name) = &self.data {
+            Some(Symbol { name: name.clone(), id: self.id })
+        } else { None }
+    }
+}
+
+/// A variable identifier, along with the unique ID of its declaration.
+/// IDs are assigned in scope resolution, starting from 1. Shadowed variables differ by ID.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Symbol {
+    pub name: String,
+    pub id: usize,
+}
+impl Symbol {
+    pub fn new (name: String) -> Self {
+        Self { name, id: 0 }
+    }
 }
 pub type Tokens = Vec<Token>;
 
@@ -118,6 +140,12 @@ impl LElem {
             LElem::Token(t) => &t.t,
         }
     }
+    pub fn is_evaluable (&self) -> bool {
+        match self {
+            LElem::Node(n)  => NodeT::from(&n.data).is_evaluable(),
+            LElem::Token(t) => TokenT::from(&t.data).is_evaluable(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -5,15 +5,35 @@ use strum_macros::{EnumIter, EnumDiscriminants};
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub enum Type {
     Void,
+    /// i64
     Int,
+    I32,
+    U8,
+    U64,
+    /// f64
+    Float,
     Bool,
     String,
+    /// Pointer to a length prefixed run of elements in linear memory
+    Array(Box<Type>),
+    /// Pointer to a growable list header in linear memory
+    List(Box<Type>),
+    /// Type variable of generic STD lib signatures. Spelled 'T'
+    Generic,
 }
 impl Default for Type {
     fn default() -> Self {
         Self::Void
     }
 }
+impl Type {
+    pub fn is_int (&self) -> bool {
+        matches!(self, Type::Int | Type::I32 | Type::U8 | Type::U64)
+    }
+    pub fn is_signed (&self) -> bool {
+        matches!(self, Type::Int | Type::I32)
+    }
+}
 
 
 #[allow(non_camel_case_types)]
@@ -29,21 +49,36 @@ pub enum TokenData {
     x,
 
     K_LET,
+    K_MUT,
     K_FUNC,
     K_RETURN,
     K_IF,
+    K_ELSE,
+    K_WHILE,
+    K_BREAK,
+    K_CONTINUE,
+    K_FOR,
+    K_IN,
+    K_STEP,
+    K_AS,
 
     IDENT(String),
     ASSIGN,
+    OP_ASSIGN(String),
 
     INT(i64),
+    /// Normalized literal text. Kept as text, since f64 is not Eq.
+    FLOAT(String),
     STR_LIT(String),
     BOOL(bool),
 
+    // Binary operators, numbered by increasing precedence.
     OP1_b(String),
     OP2_b(String),
-    OP3_n(String),
-    OP4_n(String),
+    OP3_b(String),
+    OP4_b(String),
+    OP5_n(String),
+    OP6_n(String),
     OP_UNARY(String),
     TYPE(Type),
     
@@ -51,9 +86,14 @@ pub enum TokenData {
     PAREN_R,
     CURLY_L,
     CURLY_R,
+    BRACKET_L,
+    BRACKET_R,
     COMMA, 
     COLON,
     ARROW,
+    RANGE,
+    /// Statement terminator. Either an explicit ';', or inserted by the lexer at line breaks.
+    SEMI,
 }
 
 
@@ -82,11 +122,39 @@ pub enum NodeData {
     If {
         expr: ChildRef,
         block: ChildRef,
+        /// Either a Block, or a nested If for 'else if' chains.
+        else_block: Option<ChildRef>,
+    },
+    While {
+        expr: ChildRef,
+        block: ChildRef,
+    },
+    For {
+        ident: Symbol,
+        from: ChildRef,
+        to: ChildRef,
+        step: Option<ChildRef>,
+        block: ChildRef,
     },
+    Break,
+    Continue,
     Declaration { 
-        ident: String,
+        ident: Symbol,
+        mutable: bool,
+        /// Optional type annotation
+        t: Option<Type>,
+        expr: ChildRef,
+    },
+    Assignment {
+        ident: Symbol,
         expr: ChildRef,
     },
+    /// Assignment into an array element, 'expr[index] = value'
+    IndexAssignment {
+        expr: ChildRef,
+        index: ChildRef,
+        value: ChildRef,
+    },
     Expr {
         t1: ChildRef,
         t2: ChildRef,
@@ -96,21 +164,37 @@ pub enum NodeData {
         op: String,
         t: ChildRef,
     },
-    T1, T2, T3, TBase, 
+    /// Explicit conversion of `expr` into type `t`
+    Cast {
+        t: Type,
+        expr: ChildRef,
+    },
+    T1, T2, T3, T4, T5, T6, T7, TBase, 
 
-    Return { expr: ChildRef },
+    /// Array literal
+    Array { v: Vec<ChildRef> },
+    Index {
+        expr: ChildRef,
+        index: ChildRef,
+    },
+
+    Return { expr: Option<ChildRef> },
     Invoke {
         ident: String,
         args: Vec<ChildRef>,
     },
     Args ,
-    Params { v: Vec<(String, Type)> },
+    Params {
+        v: Vec<(Symbol, Type)>,
+        /// Start of each parameter's identifier
+        starts: Vec<usize>,
+    },
 }
 
 impl NodeT {
     pub fn is_evaluable (&self) -> bool {
         match self {
-            Expr | UExpr | Invoke => true,
+            Expr | UExpr | Cast | Invoke | Block | If | Array | Index => true,
             _ => false,
         }
     }
@@ -118,7 +202,7 @@ impl NodeT {
 impl TokenT {
     pub fn is_evaluable (&self) -> bool {
         match self {
-            INT | BOOL | IDENT | STR_LIT => true,
+            INT | FLOAT | BOOL | IDENT | STR_LIT => true,
             _ => false,
         }
     }
@@ -167,36 +251,96 @@ impl Productions {
                     vec![
                         Token(K_FUNC), Token(IDENT), Token(PAREN_L), Node(Params), Token(PAREN_R),
                         Token(ARROW), Token(TYPE), 
-                        Node(Block),
+                        Node(Block), Token(SEMI),
                     ],
                     vec![
                         Token(K_FUNC), Token(IDENT), Token(PAREN_L), Token(PAREN_R),
                         Token(ARROW), Token(TYPE),
-                        Node(Block),
+                        Node(Block), Token(SEMI),
                     ],
                 ]
             ),
             (
                 Statement,
+                // Every statement is terminated, so that a line starting with '(', '[' or '-' 
+                // does not continue the previous one. On a single line, the parser inserts 
+                // terminators before tokens that cannot continue the statement.
                 vec![
-                    vec![Node(Declaration)],
-                    vec![Node(Block)],
-                    vec![Node(Invoke)],
-                    vec![Node(If)],
-                    vec![Node(Return)]
+                    vec![Node(Declaration), Token(SEMI)],
+                    vec![Node(Assignment), Token(SEMI)],
+                    vec![Node(IndexAssignment), Token(SEMI)],
+                    // Includes Blocks & Ifs
+                    vec![Node(Expr), Token(SEMI)],
+                    vec![Node(While), Token(SEMI)],
+                    vec![Node(For), Token(SEMI)],
+                    vec![Node(Break), Token(SEMI)],
+                    vec![Node(Continue), Token(SEMI)],
+                    vec![Node(Return), Token(SEMI)]
                 ]
             ),
             ( 
                 Declaration,
-                vec![vec![ Token(K_LET), Token(IDENT), Token(ASSIGN), Node(Expr) ]]
+                vec![
+                    vec![ Token(K_LET), Token(IDENT), Token(ASSIGN), Node(Expr) ],
+                    vec![ Token(K_LET), Token(K_MUT), Token(IDENT), Token(ASSIGN), Node(Expr) ],
+                    vec![ Token(K_LET), Token(IDENT), Token(COLON), Token(TYPE), Token(ASSIGN), Node(Expr) ],
+                    vec![ Token(K_LET), Token(K_MUT), Token(IDENT), Token(COLON), Token(TYPE), Token(ASSIGN), Node(Expr) ],
+                ]
+            ),
+            ( 
+                Assignment,
+                vec![
+                    vec![ Token(IDENT), Token(ASSIGN), Node(Expr) ],
+                    vec![ Token(IDENT), Token(OP_ASSIGN), Node(Expr) ],
+                ]
+            ),
+            ( 
+                IndexAssignment,
+                vec![
+                    vec![ Node(TBase), Token(BRACKET_L), Node(Expr), Token(BRACKET_R), Token(ASSIGN), Node(Expr) ],
+                ]
             ),
             ( 
                 If,
-                vec![vec![ Token(K_IF), Node(Expr), Node(Block) ]]
+                vec![
+                    vec![ Token(K_IF), Node(Expr), Node(Block) ],
+                    vec![ Token(K_IF), Node(Expr), Node(Block), Token(K_ELSE), Node(Block) ],
+                    vec![ Token(K_IF), Node(Expr), Node(Block), Token(K_ELSE), Node(If) ],
+                ]
+            ),
+            ( 
+                While,
+                vec![vec![ Token(K_WHILE), Node(Expr), Node(Block) ]]
+            ),
+            ( 
+                For,
+                vec![
+                    vec![ 
+                        Token(K_FOR), Token(IDENT), Token(K_IN), Node(Expr), Token(RANGE), Node(Expr),
+                        Node(Block)
+                    ],
+                    vec![ 
+                        Token(K_FOR), Token(IDENT), Token(K_IN), Node(Expr), Token(RANGE), Node(Expr),
+                        Token(K_STEP), Node(Expr),
+                        Node(Block)
+                    ],
+                ]
+            ),
+            ( 
+                Break,
+                vec![vec![ Token(K_BREAK) ]]
+            ),
+            ( 
+                Continue,
+                vec![vec![ Token(K_CONTINUE) ]]
             ),
             ( 
                 Return,
-                vec![vec![ Token(K_RETURN), Node(Expr) ]]
+                vec![
+                    vec![ Token(K_RETURN), Node(Expr) ],
+                    // Bare return. A statement on the next line is not taken as its value
+                    vec![ Token(K_RETURN) ],
+                ]
             ),
             (
                 Invoke,
@@ -205,36 +349,71 @@ impl Productions {
                     vec![ Token(IDENT), Token(PAREN_L), Token(PAREN_R) ]
                 ]
             ),
+            ( 
+                Array,
+                vec![ vec![ Token(BRACKET_L), Node(Args), Token(BRACKET_R) ] ]
+            ),
+            ( 
+                Index,
+                vec![ vec![ Node(TBase), Token(BRACKET_L), Node(Expr), Token(BRACKET_R) ] ]
+            ),
             ( 
                 Args,
                 vec![ vec![Node(Args), Token(COMMA), Node(Expr)], vec![Node(Expr)] ]
             ),
-            // Expressions
+            // Expressions. One level per precedence, from '||' down to unary operators.
+            // Left recursive, so that operators are left associative.
             ( 
                 Expr,
-                vec![vec![ Node(T1), Token(OP1_b), Node(Expr)], vec![ Node(T1) ]]
+                vec![vec![ Node(Expr), Token(OP1_b), Node(T1)], vec![ Node(T1) ]]
             ),
             ( 
                 T1,
-                vec![vec![ Node(T2), Token(OP2_b), Node(T1)], vec![ Node(T2) ]],
+                vec![vec![ Node(T1), Token(OP2_b), Node(T2)], vec![ Node(T2) ]],
             ),
             ( 
                 T2,
-                vec![vec![ Node(T3), Token(OP3_n), Node(T2)], vec![ Node(T3) ]],
+                vec![vec![ Node(T2), Token(OP3_b), Node(T3)], vec![ Node(T3) ]],
             ),
             ( 
                 T3,
-                vec![vec![ Node(TBase), Token(OP4_n), Node(T3)], vec![ Node(TBase) ]],
+                vec![vec![ Node(T3), Token(OP4_b), Node(T4)], vec![ Node(T4) ]],
+            ),
+            ( 
+                T4,
+                vec![vec![ Node(T4), Token(OP5_n), Node(T5)], vec![ Node(T5) ]],
+            ),
+            ( 
+                T5,
+                vec![vec![ Node(T5), Token(OP6_n), Node(T6)], vec![ Node(T6) ]],
+            ),
+            ( 
+                T6,
+                vec![vec![ Node(T6), Token(K_AS), Token(TYPE)], vec![ Node(T7) ]],
+            ),
+            // Prefix operators bind looser than indexing, e.g. '-a[0]' is '-(a[0])'
+            ( 
+                T7,
+                vec![
+                    vec![ Token(OP_UNARY), Node(T7) ],
+                    vec![ Token(OP5_n), Node(T7) ],
+                    vec![ Node(TBase) ],
+                ],
             ),
             ( 
                 TBase,
                 vec![
                     vec![ Node(Invoke) ],
+                    vec![ Node(Block) ],
+                    vec![ Node(If) ],
+                    vec![ Node(Array) ],
+                    vec![ Node(Index) ],
                     vec![ Token(INT) ],
+                    vec![ Token(FLOAT) ],
                     vec![ Token(BOOL) ],
                     vec![ Token(IDENT) ],
                     vec![ Token(STR_LIT) ],
-                    vec![ Token(OP_UNARY), Node(TBase) ],
+                    vec![ Token(TYPE), Token(PAREN_L), Node(Expr), Token(PAREN_R) ],
                     vec![ Token(PAREN_L), Node(Expr), Token(PAREN_R) ]
                 ],
             ),
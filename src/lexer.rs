@@ -1,12 +1,12 @@
 use nom::{
     IResult,
     branch::alt,
-    error::ParseError,
-    combinator::{map_res, recognize},
-    multi::{many1, many0},
-    bytes::complete::{tag, take_until},
-    character::complete::{char, digit1, multispace0, alpha1, alphanumeric1, one_of}, 
-    sequence::{terminated, delimited, pair}
+    error::{ParseError, ErrorKind},
+    combinator::{map_res, recognize, not, opt, cut, value, consumed},
+    multi::{many1, many0, many0_count},
+    bytes::complete::{tag, is_not},
+    character::complete::{char, digit1, multispace1, alpha1, alphanumeric1, one_of, satisfy, anychar}, 
+    sequence::{terminated, delimited, pair, tuple}
 };
 
 use super::*;
@@ -21,7 +21,38 @@ where
     E: ParseError<Span<'a>>,
     F: nom::Parser<Span<'a>, O, E>
 {
-    delimited(multispace0, parser, multispace0)
+    delimited(skip, parser, skip)
+}
+
+
+/// Skips whitespace & comments.
+fn skip<'a, E: ParseError<Span<'a>>> (input: Span<'a>) -> IResult<Span<'a>, (), E> {
+    value((), many0_count(alt((
+        multispace1,
+        line_comment,
+        block_comment,
+    ))))(input)
+}
+
+
+fn line_comment<'a, E: ParseError<Span<'a>>> (input: Span<'a>) -> IResult<Span<'a>, Span<'a>, E> {
+    recognize(pair(tag("//"), opt(is_not("\r\n"))))(input)
+}
+
+
+/// Block comments may be nested. An unterminated comment is unrecoverable, and is reported at
+/// its opening.
+fn block_comment<'a, E: ParseError<Span<'a>>> (input: Span<'a>) -> IResult<Span<'a>, Span<'a>, E> {
+    let body = many0_count(alt((
+        block_comment,
+        is_not("/*"),
+        recognize(pair(not(tag("*/")), anychar)),
+    )));
+    recognize(pair(tag("/*"), cut(terminated(body, tag("*/")))))(input)
+        .map_err(|e| match e {
+            nom::Err::Failure(_) => nom::Err::Failure(E::from_error_kind(input, ErrorKind::Tag)),
+            e => e
+        })
 }
 
 
@@ -44,6 +75,36 @@ fn int (input: Span) -> IResult<Span, Token> {
         (input)
 }
 
+/// Float literals. Requires a fractional part or an exponent, e.g. '1.5', '2.0e3' or '1e-3'. 
+/// A fractional part needs digits after the dot, leaving '0..n' as a range.
+fn float (input: Span) -> IResult<Span, Token> {
+    fn digits (input: Span) -> IResult<Span, Span> {
+        recognize(many1(terminated(digit1, many0(char('_')))))(input)
+    }
+    fn exponent (input: Span) -> IResult<Span, Span> {
+        recognize(tuple((one_of("eE"), opt(one_of("+-")), digit1)))(input)
+    }
+    ws(map_res(
+        recognize(pair(
+            digits,
+            alt((
+                recognize(tuple((char('.'), digits, opt(exponent)))),
+                exponent,
+            ))
+        )),
+        |s: Span| -> Result<Token, std::num::ParseFloatError> {
+            let n = s.fragment().replace('_', "").parse::<f64>()?;
+
+            Ok(Token {
+                start: s.location_offset(),
+                end: s.location_offset() + s.len(),
+                data: TokenData::FLOAT(format!("{:?}", n))
+            })
+        }
+    ))
+        (input)
+}
+
 
 fn bol (input: Span) -> IResult<Span, Token> {
     ws(map_res(
@@ -61,30 +122,112 @@ fn bol (input: Span) -> IResult<Span, Token> {
 }
 
 fn str_lit (input: Span) -> IResult<Span, Token> {
+    let content = recognize(many0_count(alt((
+        is_not("\"\\"),
+        recognize(pair(char('\\'), anychar)),
+    ))));
     ws(map_res(
-        delimited(char('"'), take_until("\""), char('"')),
-        |s: Span| -> Result<Token, std::str::ParseBoolError> {
+        delimited(char('"'), content, char('"')),
+        |s: Span| -> Result<Token, ()> {
             Ok(Token {
                 start: s.location_offset(),
                 end: s.location_offset() + s.len(),
-                data: TokenData::STR_LIT(s.to_string())
+                data: TokenData::STR_LIT(unescape(s.fragment())?)
             })
         }
     ))(input)
 }
 
+/// Decodes escape sequences in a string literal's contents.
+/// Supports '\n', '\r', '\t', '\"', '\\' and unicode escapes '\u{...}'.
+fn unescape (s: &str) -> Result<String, ()> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue
+        }
+        let c = match chars.next().ok_or(())? {
+            'n'  => '\n',
+            'r'  => '\r',
+            't'  => '\t',
+            '"'  => '"',
+            '\\' => '\\',
+            'u'  => {
+                if chars.next() != Some('{') { return Err(()) }
+                let hex: String = std::iter::from_fn(|| chars.next_if(char::is_ascii_hexdigit)).collect();
+                if chars.next() != Some('}') { return Err(()) }
+                if hex.is_empty() || hex.len() > 6 { return Err(()) }
+                let n = u32::from_str_radix(&hex, 16).map_err(|_| ())?;
+                char::from_u32(n).ok_or(())?
+            },
+            _ => return Err(())
+        };
+        out.push(c);
+    }
+    Ok(out)
+}
+
 
 // Used by library signature annotation parser.
 pub fn types (input: Span) -> IResult<Span, Token> {
+    alt((scalar_type, array_type, list_type))(input)
+}
+
+/// Array type, e.g. '[i64]'. Nests, e.g. '[[u8]]'.
+fn array_type (input: Span) -> IResult<Span, Token> {
     ws(map_res(
-        alt((
-            tag("i64"),
-            tag("void"), 
-            tag("str"),
-        )),
+        consumed(delimited(char('['), types, char(']'))),
+        |(s, t): (Span, Token)| -> Result<Token, nom::error::Error<Span>> {
+            let TokenData::TYPE(t) = t.data else { unreachable!() };
+            Ok(Token {
+                start: s.location_offset(),
+                end: s.location_offset() + s.len(),
+                data: TokenData::TYPE(Type::Array(Box::new(t)))
+            })
+        }
+    ))(input)
+}
+
+/// Growable list type, e.g. 'list<i64>'.
+fn list_type (input: Span) -> IResult<Span, Token> {
+    ws(map_res(
+        consumed(delimited(tag("list<"), types, char('>'))),
+        |(s, t): (Span, Token)| -> Result<Token, nom::error::Error<Span>> {
+            let TokenData::TYPE(t) = t.data else { unreachable!() };
+            Ok(Token {
+                start: s.location_offset(),
+                end: s.location_offset() + s.len(),
+                data: TokenData::TYPE(Type::List(Box::new(t)))
+            })
+        }
+    ))(input)
+}
+
+fn scalar_type (input: Span) -> IResult<Span, Token> {
+    ws(map_res(
+        terminated(
+            alt((
+                tag("i64"),
+                tag("i32"),
+                tag("u64"),
+                tag("u8"),
+                tag("f64"),
+                tag("bool"),
+                tag("void"), 
+                tag("str"),
+            )),
+            not(satisfy(|c| c.is_alphanumeric() || c == '_'))
+        ),
         |s: Span| -> Result<Token, nom::error::Error<Span>> {
             let t = match s.fragment() {
                 &"i64"  => Type::Int,
+                &"i32"  => Type::I32,
+                &"u64"  => Type::U64,
+                &"u8"   => Type::U8,
+                &"f64"  => Type::Float,
+                &"bool" => Type::Bool,
                 &"void" => Type::Void,
                 &"str" => Type::String,
                 _   => unreachable!()
@@ -103,12 +246,24 @@ fn tags (input: Span) -> IResult<Span, Token> {
     ws(map_res(
         alt((
             tag("="),
-            tag("->")
+            tag("->"),
+            tag(".."),
+            tag("+="),
+            tag("-="),
+            tag("*="),
+            tag("/="),
+            tag("%="),
         )),
         |s: Span| -> Result<Token, nom::error::Error<Span>> {
             let data = match s.fragment() {
                 &"="  => TokenData::ASSIGN,
                 &"->" => TokenData::ARROW,
+                &".." => TokenData::RANGE,
+                &"+=" => TokenData::OP_ASSIGN("+".to_owned()),
+                &"-=" => TokenData::OP_ASSIGN("-".to_owned()),
+                &"*=" => TokenData::OP_ASSIGN("*".to_owned()),
+                &"/=" => TokenData::OP_ASSIGN("/".to_owned()),
+                &"%=" => TokenData::OP_ASSIGN("%".to_owned()),
                 _   => unreachable!()
             };
             Ok(Token {
@@ -123,15 +278,18 @@ fn tags (input: Span) -> IResult<Span, Token> {
 
 fn symbols (input: Span) -> IResult<Span, Token> {
     ws(map_res(
-        recognize(one_of("(){},:")),
+        recognize(one_of("(){}[],:;")),
         |s: Span| -> Result<Token, nom::error::Error<Span>> {
             let data = match s.fragment() {
                 &"(" => TokenData::PAREN_L,
                 &")" => TokenData::PAREN_R,
                 &"{" => TokenData::CURLY_L,
                 &"}" => TokenData::CURLY_R,
+                &"[" => TokenData::BRACKET_L,
+                &"]" => TokenData::BRACKET_R,
                 &"," => TokenData::COMMA,
                 &":" => TokenData::COLON,
+                &";" => TokenData::SEMI,
                 _   => unreachable!()
             };
             Ok(Token {
@@ -144,10 +302,12 @@ fn symbols (input: Span) -> IResult<Span, Token> {
 }
 
 
+/// Keywords must end on a word boundary, so that identifiers such as `iffy` or `breakpoint` are
+/// not split into a keyword and a trailing identifier.
 fn keyword<'a>(keyword: &'static str, token: TokenData) -> impl FnMut(Span<'a>) -> IResult<Span<'a>, Token, nom::error::Error<Span<'a>>> 
 {
     ws(map_res(
-        tag(keyword),
+        terminated(tag(keyword), not(satisfy(|c| c.is_alphanumeric() || c == '_'))),
         move |s: Span| -> Result<Token, nom::error::Error<Span<'a>>> {
             Ok(Token {
                 start: s.location_offset(),
@@ -183,15 +343,23 @@ fn op_tags (input: Span) -> IResult<Span, Token> {
         recognize(alt((
             tag("=="),
             tag("!="),
+            tag("<="),
+            tag(">="),
+            tag("<"),
+            tag(">"),
             tag("&&"),
             tag("||")
         ))),
         |s: Span| -> Result<Token, nom::error::Error<Span>> {
             let data = match s.fragment() {
-                &"==" => TokenData::OP1_b("==".to_owned()),
-                &"!=" => TokenData::OP1_b("!=".to_owned()),
+                &"||" => TokenData::OP1_b("||".to_owned()),
                 &"&&" => TokenData::OP2_b("&&".to_owned()),
-                &"||" => TokenData::OP2_b("||".to_owned()),
+                &"==" => TokenData::OP3_b("==".to_owned()),
+                &"!=" => TokenData::OP3_b("!=".to_owned()),
+                &"<=" => TokenData::OP4_b("<=".to_owned()),
+                &">=" => TokenData::OP4_b(">=".to_owned()),
+                &"<"  => TokenData::OP4_b("<".to_owned()),
+                &">"  => TokenData::OP4_b(">".to_owned()),
                 s @ _ => panic!("Unknown operator tag '{}'", s)
             };
             Ok(Token {
@@ -206,13 +374,14 @@ fn op_tags (input: Span) -> IResult<Span, Token> {
 
 fn op_symbols (input: Span) -> IResult<Span, Token> {
     ws(map_res(
-        recognize(one_of("/*+-")),
+        recognize(one_of("/*%+-")),
         |s: Span| -> Result<Token, nom::error::Error<Span>> {
             let data = match s.fragment() {
-                &"*" => TokenData::OP4_n("*".to_owned()),
-                &"/" => TokenData::OP4_n("/".to_owned()),
-                &"+" => TokenData::OP3_n("+".to_owned()),
-                &"-" => TokenData::OP3_n("-".to_owned()),
+                &"*" => TokenData::OP6_n("*".to_owned()),
+                &"/" => TokenData::OP6_n("/".to_owned()),
+                &"%" => TokenData::OP6_n("%".to_owned()),
+                &"+" => TokenData::OP5_n("+".to_owned()),
+                &"-" => TokenData::OP5_n("-".to_owned()),
                 s @ _ => panic!("Unknown operator symbol '{}'", s)
             };
             Ok(Token {
@@ -271,14 +440,26 @@ pub fn tokenize (source: &String) -> Result<Tokens, LexerError> {
     // NOTE: Rule for parser order. More general parsers should go in the bottom, that way the more
     // specific ones will filter first, before the general ones capture it.
     
-    let parsers = (
+    let keywords = alt((
         keyword("let", TokenData::K_LET),
+        keyword("mut", TokenData::K_MUT),
         keyword("func", TokenData::K_FUNC),
         keyword("return", TokenData::K_RETURN),
         keyword("if", TokenData::K_IF),
+        keyword("else", TokenData::K_ELSE),
+        keyword("while", TokenData::K_WHILE),
+        keyword("break", TokenData::K_BREAK),
+        keyword("continue", TokenData::K_CONTINUE),
+        keyword("for", TokenData::K_FOR),
+        keyword("in", TokenData::K_IN),
+        keyword("as", TokenData::K_AS),
+    ));
+    let parsers = (
+        keywords,
 
         bol,
         types,
+        float,
         int,
 
         op_tags,
@@ -291,21 +472,183 @@ pub fn tokenize (source: &String) -> Result<Tokens, LexerError> {
         ident,
     );
     let mut parser = many1(alt(parsers));
-    let res = parser(span).unwrap();
-
-    // Some unrecognized token
+    let res = match parser(span) {
+        Ok(res) => res,
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => 
+            return Err( LexerError { pos: e.input.location_offset() } ),
+        Err(nom::Err::Incomplete(_)) => unreachable!("complete parsers should not request more input"),
+    };
+
+    // Some unrecognized token. Record position where lexing failed.
     if !res.0.is_empty() {
-        
-        // Record position where lexing failed.
-        let pos = if let Some(last) = res.1.last() { 
-            last.end+1
-        } else { 0 };
-
-        return Err( LexerError { pos } );
+        return Err( LexerError { pos: res.0.location_offset() } );
     }
 
-    let mut tokens = res.1;
+    let mut tokens = terminate(source, res.1);
     tokens.push(Token{ start: source.len(), end: source.len(), data: TokenData::EOF});
     Ok(tokens)
 }
 
+
+/// Inserts statement terminators, so they can be left out. A terminator follows a token that 
+/// could end a statement, when the token is followed by a line break, a closing '}' or the end
+/// of the source. Line breaks within '(..)' & '[..]', or before an 'else', do not terminate.
+fn terminate (source: &str, tokens: Tokens) -> Tokens {
+    let ends_statement = |t: &Token| matches!(t.data, 
+        TokenData::IDENT(_) | TokenData::INT(_) | TokenData::FLOAT(_) | TokenData::STR_LIT(_) |
+        TokenData::BOOL(_) | TokenData::TYPE(_) | 
+        TokenData::PAREN_R | TokenData::BRACKET_R | TokenData::CURLY_R | 
+        TokenData::K_RETURN | TokenData::K_BREAK | TokenData::K_CONTINUE
+    );
+
+    let mut out = Vec::with_capacity(tokens.len());
+    // Open delimiters
+    let mut open = vec![];
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(t) = tokens.next() {
+        match t.data {
+            TokenData::PAREN_L | TokenData::BRACKET_L | TokenData::CURLY_L => open.push(t.data.clone()),
+            TokenData::PAREN_R | TokenData::BRACKET_R | TokenData::CURLY_R => { open.pop(); },
+            _ => ()
+        }
+        let terminated = ends_statement(&t) && match tokens.peek() {
+            None => true,
+            Some(Token { data: TokenData::CURLY_R, .. }) => true,
+            Some(Token { data: TokenData::K_ELSE, .. }) => false,
+            Some(next) => 
+                matches!(open.last(), None | Some(TokenData::CURLY_L)) &&
+                source[t.end..next.start].contains('\n'),
+        };
+        let end = t.end;
+        out.push(t);
+        if terminated {
+            out.push(Token { start: end, end, data: TokenData::SEMI });
+        }
+    }
+    out
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lex (source: &str) -> Result<Tokens, LexerError> {
+        tokenize(&source.to_owned())
+    }
+
+    #[test]
+    fn comments_are_skipped () {
+        let tokens = lex("let x = 1 // trailing\n/* block /* nested */ still comment */ x").ok().unwrap();
+        let data: Vec<_> = tokens.into_iter().map(|t| t.data).collect();
+        assert_eq!(data, vec![
+            TokenData::K_LET,
+            TokenData::IDENT("x".to_owned()),
+            TokenData::ASSIGN,
+            TokenData::INT(1),
+            TokenData::SEMI,
+            TokenData::IDENT("x".to_owned()),
+            TokenData::SEMI,
+            TokenData::EOF,
+        ]);
+    }
+
+    #[test]
+    fn statement_terminators () {
+        let data = |source| -> Vec<_> { lex(source).ok().unwrap().into_iter().map(|t| t.data).collect() };
+        // Line breaks end statements, so the next line is not an argument list or a subtraction
+        assert_eq!(data("f(x)\n(y)\n-1"), vec![
+            TokenData::IDENT("f".to_owned()),
+            TokenData::PAREN_L,
+            TokenData::IDENT("x".to_owned()),
+            TokenData::PAREN_R,
+            TokenData::SEMI,
+            TokenData::PAREN_L,
+            TokenData::IDENT("y".to_owned()),
+            TokenData::PAREN_R,
+            TokenData::SEMI,
+            TokenData::OP5_n("-".to_owned()),
+            TokenData::INT(1),
+            TokenData::SEMI,
+            TokenData::EOF,
+        ]);
+        // Not after an operator, within parentheses, or before 'else'. Always before '}'
+        assert_eq!(data("x +\n f(1,\n 2)"), data("x + f(1, 2)"));
+        assert_eq!(data("if a { b }\nelse { return }"), data("if a { b; } else { return; };"));
+    }
+
+    #[test]
+    fn relational_operators () {
+        let tokens = lex("a<=b>=c<d>e").ok().unwrap();
+        let ops: Vec<_> = tokens.into_iter().filter_map(|t| match t.data {
+            TokenData::OP4_b(op) => Some(op),
+            _ => None
+        }).collect();
+        assert_eq!(ops, vec!["<=", ">=", "<", ">"]);
+    }
+
+    #[test]
+    fn offsets_preserved_across_comments () {
+        let source = "/* a */ let // b\n  y = 2";
+        let tokens = lex(source).ok().unwrap();
+        let y = &tokens[1];
+        assert_eq!(y.data, TokenData::IDENT("y".to_owned()));
+        assert_eq!(&source[y.start..y.end], "y");
+        assert_eq!(tokens[3].start, source.len()-1);
+    }
+
+    #[test]
+    fn string_escapes () {
+        let tokens = lex(r#"let s = "a\"b\\c\n\t\u{e9}\u{1F600}" "#).ok().unwrap();
+        assert_eq!(tokens[3].data, TokenData::STR_LIT("a\"b\\c\n\t\u{e9}\u{1F600}".to_owned()));
+        assert!(lex(r#"let s = "\q""#).is_err());
+        assert!(lex(r#"let s = "\u{110000}""#).is_err());
+        // Unclosed unicode escape
+        assert!(lex(r#"let s = "\u{41""#).is_err());
+        assert!(lex(r#"let s = "\u{41 ""#).is_err());
+    }
+
+    #[test]
+    fn types_need_word_boundary () {
+        let tokens = lex("let boolean = u8(1) let str_x = 1").ok().unwrap();
+        assert_eq!(tokens[1].data, TokenData::IDENT("boolean".to_owned()));
+        assert_eq!(tokens[3].data, TokenData::TYPE(Type::U8));
+        assert_eq!(tokens[8].data, TokenData::IDENT("str_x".to_owned()));
+    }
+
+    #[test]
+    fn array_types () {
+        // Brackets around an expression are an array literal, not a type
+        let tokens = lex("let a: [[i64]] = [[1], [i64(2)]]").ok().unwrap();
+        assert_eq!(tokens[3].data, TokenData::TYPE(Type::Array(Box::new(Type::Array(Box::new(Type::Int))))));
+        assert_eq!(tokens[5].data, TokenData::BRACKET_L);
+        assert_eq!(tokens[10].data, TokenData::BRACKET_L);
+        assert_eq!(tokens[11].data, TokenData::TYPE(Type::Int));
+    }
+
+    #[test]
+    fn list_types () {
+        // 'list' alone is an identifier, e.g. the 'list()' constructor
+        let tokens = lex("let xs: list<[u8]> = list() xs < list").ok().unwrap();
+        assert_eq!(tokens[3].data, TokenData::TYPE(Type::List(Box::new(Type::Array(Box::new(Type::U8))))));
+        assert_eq!(tokens[5].data, TokenData::IDENT("list".to_owned()));
+        assert_eq!(tokens[10].data, TokenData::IDENT("list".to_owned()));
+    }
+
+    #[test]
+    fn float_literals () {
+        let tokens = lex("let a = 1.5 + 1e-3 + 2_000.25E2 for i in 0..10 {}").ok().unwrap();
+        assert_eq!(tokens[3].data, TokenData::FLOAT("1.5".to_owned()));
+        assert_eq!(tokens[5].data, TokenData::FLOAT("0.001".to_owned()));
+        assert_eq!(tokens[7].data, TokenData::FLOAT("200025.0".to_owned()));
+        assert_eq!(tokens[11].data, TokenData::INT(0));
+        assert_eq!(tokens[12].data, TokenData::RANGE);
+    }
+
+    #[test]
+    fn unterminated_block_comment () {
+        let source = "let x = 1 /* open /* nested */";
+        assert_eq!(lex(source).err().unwrap().pos, 10);
+    }
+}
@@ -24,8 +24,9 @@ pub type CilantroError = Box<dyn CilantroErrorTrait>;
 
 
 
-/// Runs Lexer, Parser, Interpreter, and Visualizer
-pub fn compile (source: &String) -> Result<String, CilantroError> {
+/// Runs Lexer, Parser, Interpreter, and Visualizer.
+/// Returns the generated code, along with warnings that did not fail the compilation.
+pub fn compile (source: &String) -> Result<(String, Vec<CilantroError>), CilantroError> {
 
     let tokens = lexer::tokenize(source)
         .map_err(|e| -> CilantroError { Box::new(e) })?;
@@ -44,14 +45,19 @@ pub fn compile (source: &String) -> Result<String, CilantroError> {
     println!("Abstract Syntax Tree:");
     nodes.iter().for_each(|n| print!("{n}"));
 
+    let warnings = semantics::lint(&nodes)
+        .into_iter()
+        .map(|w| -> CilantroError { Box::new(w) })
+        .collect();
+
     println!("Generating WASI...");
-    let code = codegen::gen(nodes);
+    let code = codegen::gen(nodes, source);
     /*
     println!("Generated code:");
     println!("{code}");
     */ 
 
-    Ok(code)
+    Ok((code, warnings))
 }
 
 
@@ -61,6 +67,7 @@ pub fn compile (source: &String) -> Result<String, CilantroError> {
 #[wasm_bindgen]
 pub fn compile_web (source: String) -> Result<String, String> {
     compile(&source)
+        .map(|(code, _)| code)
         .map_err(|err| err.fmt(&source).expect("error formating failed.").clone())
 }
 
@@ -24,7 +24,12 @@ fn main () {
     println!("Transpiling...");
     // Compile & print out error
     let code = match cilantro::compile(&source) {
-        Ok(code) => code,
+        Ok((code, warnings)) => {
+            for warning in warnings {
+                print!("{}", warning.fmt(&source).expect("Warning formatting panicked."));
+            }
+            code
+        },
         Err(err) => {
             print!("{}", err.fmt(&source).expect("Error formatting panicked."));
             panic!();
@@ -63,8 +68,14 @@ fn main () {
     linker.module(&mut store, "", &module).expect("Could not link");
 
     println!("Running...\n\n=== OUTPUT ===");
-    linker
+    let res = linker
         .get_default(&mut store, "").unwrap()
         .typed::<(), ()>(&store).unwrap()
-        .call(&mut store, ()).unwrap();
+        .call(&mut store, ());
+
+    // Runtime errors (e.g. division by zero) are reported by the stdlib before trapping.
+    if let Err(err) = res {
+        println!("\n=== Runtime Error ===\n{}", err.root_cause());
+        std::process::exit(1);
+    }
 }
@@ -56,7 +56,9 @@ impl<'a> Parser<'a> {
     /// Parses the passed soruce.
     pub fn parse (mut self) -> Result<Vec<Node>, SyntaxError> {
         let mut l: Vec<(Elem, usize)> = vec![];
-        let mut r: Vec<_> = self.tokens.into_iter().map(|t| Elem::Token(t)).rev().collect();
+        let mut r: Vec<_> = std::mem::take(&mut self.tokens).into_iter().map(|t| Elem::Token(t)).rev().collect();
+        // Whether a terminator was inserted, & not yet shifted
+        let mut inserted = false;
 
         println!("parsing...");
         loop {
@@ -77,10 +79,31 @@ impl<'a> Parser<'a> {
 
             let action = self.table[s].get(&t.t());
 
+            // 'step' is only a keyword within a for loop's range. Elsewhere it is an identifier.
+            if let (None, Elem::Token(tok @ Token { data: TokenData::IDENT(ident), .. })) = (action, t) {
+                if ident == "step" && self.shifts(&l, TokenT::K_STEP) {
+                    let tok = Token { data: TokenData::K_STEP, ..tok.clone() };
+                    *r.last_mut().unwrap() = Elem::Token(tok);
+                    continue;
+                }
+            }
+
+            // A token that cannot continue the statement starts the next one, e.g. 'let a = 1 let b = 2'.
+            // Insert the missing terminator.
+            let terminates = self.table[s].contains_key(&ElemT::Token(TokenT::SEMI));
+            if action.is_none() && terminates && !inserted && matches!(t, Elem::Token(_)) {
+                let at = l.last().map_or(t.start(), |(e, _)| e.end());
+                r.push(Elem::Token(Token { start: at, end: at, data: TokenData::SEMI }));
+                inserted = true;
+                continue;
+            }
+
             // Unfilled cell in table should mean syntax error
             if action.is_none() {
-                // To satisfy borrow checker, since 'tokens' was moved.
-                self.tokens = vec![];
+                // Report the token that could not be parsed, rather than the inserted terminator
+                if inserted {
+                    r.pop();
+                }
                 return Err(
                         self.syntax_error(&l, &r)
                             .expect("syntax error formatting panicked.") 
@@ -91,7 +114,9 @@ impl<'a> Parser<'a> {
             match action {
                 Action::Shift(ns) => {
                     //println!("shifting to {}", ns);
-                    l.push((r.pop().unwrap(), *ns))
+                    let elem = r.pop().unwrap();
+                    inserted &= !matches!(elem, Elem::Token(_));
+                    l.push((elem, *ns))
                 }
                 Action::Reduce(p) => {
                     //println!("reducing with {}", p);
@@ -117,6 +142,27 @@ impl<'a> Parser<'a> {
         Ok(out)
     }
 
+    /// Whether token `t` would be shifted after the reductions it leads to, given the stack `l`.
+    fn shifts (&self, l: &[(Elem, usize)], t: TokenT) -> bool {
+        let mut states: Vec<_> = l.iter().map(|(_, s)| *s).collect();
+        loop {
+            let s = states.last().copied().unwrap_or(0);
+            match self.table[s].get(&ElemT::Token(t)) {
+                Some(Action::Shift(_)) => return true,
+                Some(Action::Reduce(p)) => {
+                    let p = &self.productions.v[*p];
+                    states.truncate(states.len() - p.v.len());
+                    let s = states.last().copied().unwrap_or(0);
+                    match self.table[s].get(&ElemT::Node(p.node)) {
+                        Some(Action::Shift(ns)) => states.push(*ns),
+                        _ => return false
+                    }
+                },
+                None => return false
+            }
+        }
+    }
+
     /// Creates a SyntaxError Object to propogate.
     fn syntax_error (&self, l: &Vec<(Elem, usize)>, r: &Vec<Elem>) -> Result<SyntaxError, std::fmt::Error> {
         let mut f = String::new();
@@ -192,3 +238,38 @@ fn print_stacks (l: &Vec<(Elem, usize)>, r: &Vec<Elem>) -> Result<String, std::f
     Ok(f)
 }
 
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Parses the source. Returns the number of top-level statements & functions.
+    fn roots (source: &str) -> Option<usize> {
+        let source = source.to_owned();
+        let tokens = crate::lexer::tokenize(&source).ok().expect("tokenization failed");
+        Parser::new(tokens, &source).parse().ok().map(|nodes| nodes.len())
+    }
+
+    #[test]
+    fn statement_separation () {
+        // Statements on one line need no separator, unless the next could continue the previous
+        assert_eq!(roots("let a = 1 let b = 2"), Some(2));
+        assert_eq!(roots("func f () -> void {} f()"), Some(2));
+        assert_eq!(roots("a -1"), Some(1));
+        assert_eq!(roots("a; -1"), Some(2));
+        // Line breaks always separate
+        assert_eq!(roots("a\n-1"), Some(2));
+        assert_eq!(roots("f(x)\n(a + b)"), Some(2));
+        // Not within an expression
+        assert_eq!(roots("let c = (1 2)"), None);
+    }
+
+    #[test]
+    fn contextual_step () {
+        assert_eq!(roots("let step = 2"), Some(1));
+        assert_eq!(roots("for i in 0..10 step 2 {}"), Some(1));
+        assert_eq!(roots("for step in 0..step step step {}"), Some(1));
+        assert_eq!(roots("let a = n step = 3"), Some(2));
+        assert_eq!(roots("for i in 0..10 stride 2 {}"), None);
+    }
+}
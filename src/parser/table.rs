@@ -251,12 +251,20 @@ fn make_state (prods: &Productions, states: &mut States, inherits: HashSet<Item>
                 else { make_state(prods, states, ni) };
 
             //println!("add edge: {}, shift{}", x, ns);
-            edges.insert(x, Action::Shift(ns));
+            if let Some(Action::Reduce(p)) = edges.insert(x.clone(), Action::Shift(ns)) {
+                panic!("Shift/Reduce conflict on {} with:\n{}", x, prods.v[p]);
+            }
         } else {
             if let Some(follows) = prods.follows.get(&item.node(&prods)) {
                 for t in follows {
                     //println!("add edge: {}, reduce{}", t, item.prod);
-                    edges.insert(t.clone(), Action::Reduce(item.prod));
+                    match edges.insert(t.clone(), Action::Reduce(item.prod)) {
+                        Some(Action::Shift(_)) => 
+                            panic!("Shift/Reduce conflict on {} with:\n{}", t, prods.v[item.prod]),
+                        Some(Action::Reduce(p)) if p != item.prod => 
+                            panic!("Reduce/Reduce conflict on {} between:\n{}\n{}", t, prods.v[p], prods.v[item.prod]),
+                        _ => ()
+                    }
                 }
             } else {
                 // The execution of this block means an item has been found that reduces to
@@ -2,35 +2,38 @@ mod trim;
 mod scope;
 mod extract;
 mod type_check;
+mod flow;
+mod warnings;
 
 use super::*;
 
 // TypeTable exposed for annotation parser
 // TypeError exposed for visualizer
-pub use type_check::{TypeTable, TypeError};
+pub use type_check::{TypeTable, TypeError, FuncSig, select_overload};
 pub use scope::ScopeError;
+pub use warnings::{Warning, lint};
 
 pub fn to_ast (nodes: Vec<Node>) -> Result<Vec<LNode>, CilantroError> {
     
     // Trim unecessary grammar elements.
-    let mut nodes: Vec<_> = nodes.into_iter().map(|n| {
-        if let Elem::Node(n) = n.trim() {
-            n
-        } else {
-            panic!("root should not collapse to token");
+    let nodes: Vec<_> = nodes.into_iter().map(|n| {
+        match n.trim() {
+            Elem::Node(n) => n,
+            // Expression statement of a single token, e.g. 'x'. Wrapped in a block to stay a node.
+            Elem::Token(t) => Node::make(NodeT::Block, vec![Elem::Token(t)]),
         }
     }).collect();
     
     println!("Trimmed Tree:\n");
     nodes.iter().for_each(|n| println!("{n}"));
 
+    // Extract children values & Map to Node data.
+    let mut nodes: Vec<_> = nodes.into_iter().map(|n| n.extract()).collect();
+
     // Identifier Scope Resolution 
     scope::resolve_scope(&mut nodes)
         .map_err(|err| -> CilantroError { Box::new(err) })?;
 
-    // Extract children values & Map to Node data.
-    let nodes: Vec<_> = nodes.into_iter().map(|n| n.extract()).collect();
-
     println!("Extracted Tree:\n");
     nodes.iter().for_each(|n| println!("{n}"));
 
@@ -38,6 +41,23 @@ pub fn to_ast (nodes: Vec<Node>) -> Result<Vec<LNode>, CilantroError> {
     // Type checking
     let nodes = type_check::type_check(nodes).map_err(|err| -> CilantroError { Box::new(err) })?;
 
+    // Return path analysis
+    flow::check_returns(&nodes).map_err(|err| -> CilantroError { Box::new(err) })?;
+
     Ok(nodes)
 }
 
+/// Runs the source through semantic analysis. Parsing is expected to succeed.
+#[cfg(test)]
+fn analyze (source: &str) -> Result<Vec<LNode>, CilantroError> {
+    let source = source.to_owned();
+    let tokens = lexer::tokenize(&source).ok().expect("tokenization failed");
+    let nodes  = Parser::new(tokens, &source).parse().ok().expect("parsing failed");
+    to_ast(nodes)
+}
+
+/// Whether the source passes semantic analysis.
+#[cfg(test)]
+fn passes (source: &str) -> bool {
+    analyze(source).is_ok()
+}
@@ -6,6 +6,15 @@ impl Elem {
             &t.data
         } else { panic!() }
     }
+
+    /// Extracts an expression child.
+    fn extract_expr (self) -> ChildRef {
+        assert!(self.t().is_evaluable());
+        Box::new(match self {
+            Elem::Node(n)  => LElem::Node(n.extract()),
+            Elem::Token(t) => LElem::Token(LToken::from(t))
+        })
+    }
 }
 
 impl Node {
@@ -18,14 +27,25 @@ impl Node {
     pub fn extract (mut self) -> LNode {
         let data = match self.t {
             NodeT::Declaration => {
+                // Check for 'mut' keyword
+                let mutable = self.children[0].tok_data() == &TokenData::K_MUT;
+                if mutable {
+                    self.children.remove(0);
+                }
+
                 // Declare local variable
                 let ident = if let Elem::Token(t) = &self.children[0] {
                     if let TokenData::IDENT(s) = &t.data {
-                        s.clone()
+                        Symbol::new(s.clone())
                     } else { panic!() }
                 } else { panic!() };
 
-                let expr = match self.children[1].clone() {
+                // Check for type annotation
+                let t = if let Elem::Token(Token { data: TokenData::TYPE(t), .. }) = &self.children[1] {
+                    Some(t.clone())
+                } else { None };
+
+                let expr = match self.children.last().unwrap().clone() {
                     Elem::Node(n)  => {
                         assert!(n.t.is_evaluable());
                         LElem::Node(n.extract())
@@ -36,10 +56,12 @@ impl Node {
                 
                 NodeData::Declaration { 
                     ident,
+                    mutable,
+                    t,
                     expr,
                 }
             },
-            NodeT::Return => {
+            NodeT::Assignment => {
                 // Get Expression
                 let expr = self.children.pop().unwrap();
                 assert!(expr.t().is_evaluable());
@@ -49,15 +71,57 @@ impl Node {
                 };
                 let expr = Box::new(expr);
 
+                // Get assigned variable
+                let var = if let Elem::Token(t) = self.children[0].clone() { t } 
+                    else { panic!() };
+                let ident = if let TokenData::IDENT(s) = &var.data { Symbol::new(s.clone()) } 
+                    else { panic!() };
+
+                // Desugar compound assignment: 'x += e' becomes 'x = x + e'
+                let expr = if let Some(Elem::Token(Token { data: TokenData::OP_ASSIGN(op), .. })) = self.children.get(1) {
+                    let expr = LNode {
+                        start: var.start,
+                        end: expr.end(),
+                        data: NodeData::Expr {
+                            t1: Box::new(LElem::Token(LToken::from(var))),
+                            t2: expr,
+                            op: op.clone(),
+                        },
+                        t: Type::Void
+                    };
+                    Box::new(LElem::Node(expr))
+                } else { expr };
+
+                NodeData::Assignment { ident, expr }
+            },
+            NodeT::Return => {
+                // Get Expression. Absent on a bare 'return'
+                let expr = self.children.pop().map(|expr| {
+                    assert!(expr.t().is_evaluable());
+                    Box::new(match expr { 
+                        Elem::Node(n)  => LElem::Node(n.extract()),
+                        Elem::Token(t) => LElem::Token(LToken::from(t))
+                    })
+                });
+
                 NodeData::Return { expr }
             },
             NodeT::If => {
-                // Get Block (in reverse since we are pop()'ing)
+                // Get Else branch, a Block or a nested If (in reverse since we are pop()'ing)
+                let else_block = if self.children.len() == 3 {
+                    if let Elem::Node(n) = self.children.pop().unwrap() {
+                        assert!(n.t.is_block() || n.t.is_if());
+                        let elem = LElem::Node(n.extract());
+                        Some( Box::new( elem ) )
+                    } else { panic!("'If' expected Block or If as else branch.") }
+                } else { None };
+
+                // Get Block
                 let block = if let Elem::Node(n) = self.children.pop().unwrap() {
                     assert!(n.t.is_block());
                     let elem = LElem::Node(n.extract());
                     Box::new( elem )
-                } else { panic!("'If' expected Block as last child.") };
+                } else { panic!("'If' expected Block after expression.") };
 
 
                 // Get Expression
@@ -70,8 +134,60 @@ impl Node {
                 let expr = Box::new(expr);
                 
 
-                NodeData::If { expr, block }
+                NodeData::If { expr, block, else_block }
+            },
+            NodeT::While => {
+                // Get Block (in reverse since we are pop()'ing)
+                let block = if let Elem::Node(n) = self.children.pop().unwrap() {
+                    assert!(n.t.is_block());
+                    let elem = LElem::Node(n.extract());
+                    Box::new( elem )
+                } else { panic!("'While' expected Block as last child.") };
+
+                // Get Expression
+                let expr = self.children.pop().unwrap();
+                assert!(expr.t().is_evaluable());
+                let expr = match expr { 
+                    Elem::Node(n)  => LElem::Node(n.extract()),
+                    Elem::Token(t) => LElem::Token(LToken::from(t))
+                };
+                let expr = Box::new(expr);
+
+                NodeData::While { expr, block }
             },
+            NodeT::For => {
+                // Get loop variable
+                let ident = if let TokenData::IDENT(s) = self.children[0].tok_data() {
+                    Symbol::new(s.clone())
+                } else { panic!() };
+
+                // Get Block (in reverse since we are pop()'ing)
+                let block = if let Elem::Node(n) = self.children.pop().unwrap() {
+                    assert!(n.t.is_block());
+                    let elem = LElem::Node(n.extract());
+                    Box::new( elem )
+                } else { panic!("'For' expected Block as last child.") };
+
+                // Get range bounds & optional step
+                let mut bounds = self.children
+                    .into_iter()
+                    .skip(1)
+                    .map(|expr| {
+                        assert!(expr.t().is_evaluable());
+                        let expr = match expr { 
+                            Elem::Node(n)  => LElem::Node(n.extract()),
+                            Elem::Token(t) => LElem::Token(LToken::from(t))
+                        };
+                        Box::new(expr)
+                    });
+                let from = bounds.next().expect("'For' expected range start");
+                let to   = bounds.next().expect("'For' expected range end");
+                let step = bounds.next();
+
+                NodeData::For { ident, from, to, step, block }
+            },
+            NodeT::Break => NodeData::Break,
+            NodeT::Continue => NodeData::Continue,
             NodeT::Invoke => {
                 // Get Function name
                 let ident = if let Elem::Token(t) = &self.children[0] {
@@ -100,8 +216,30 @@ impl Node {
                             
                 NodeData::Invoke { ident, args }
             },
+            NodeT::Array => {
+                let v = self.children
+                    .into_iter()
+                    .map(|child| child.extract_expr())
+                    .collect();
+
+                NodeData::Array { v }
+            },
+            NodeT::Index => {
+                let index = self.children.pop().unwrap().extract_expr();
+                let expr  = self.children.pop().unwrap().extract_expr();
+
+                NodeData::Index { expr, index }
+            },
+            NodeT::IndexAssignment => {
+                let value = self.children.pop().unwrap().extract_expr();
+                let index = self.children.pop().unwrap().extract_expr();
+                let expr  = self.children.pop().unwrap().extract_expr();
+
+                NodeData::IndexAssignment { expr, index, value }
+            },
             NodeT::Params => {
                 let mut v = vec![];
+                let mut starts = vec![];
                 for i in 0..self.children.len()/2 {
                     let ident = 
                         if let TokenData::IDENT(s) = self.children[2*i].tok_data() { s }
@@ -109,9 +247,10 @@ impl Node {
                     let t = 
                         if let TokenData::TYPE(t) = self.children[2*i+1].tok_data() { t }
                         else { panic!() };
-                    v.push((ident.clone(), t.clone()));
+                    v.push((Symbol::new(ident.clone()), t.clone()));
+                    starts.push(self.children[2*i].start());
                 }
-                NodeData::Params{ v }
+                NodeData::Params{ v, starts }
             },
             NodeT::Expr => {
                 let t2 = match self.children.pop().unwrap() {
@@ -126,8 +265,10 @@ impl Node {
                     match t.data {
                         TokenData::OP1_b(op) |
                         TokenData::OP2_b(op) | 
-                        TokenData::OP3_n(op) | 
-                        TokenData::OP4_n(op) |
+                        TokenData::OP3_b(op) | 
+                        TokenData::OP4_b(op) | 
+                        TokenData::OP5_n(op) | 
+                        TokenData::OP6_n(op) |
                         TokenData::OP_UNARY(op) => op,
                         t @ _ => panic!("Found a non-operator token in expression: {t}")
                     }
@@ -161,7 +302,8 @@ impl Node {
 
                 let op = if let Elem::Token(t) = self.children.pop().unwrap() {
                     match t.data {
-                        TokenData::OP_UNARY(op) => op,
+                        TokenData::OP_UNARY(op) |
+                        TokenData::OP5_n(op) => op,
                         t @ _ => panic!("Found a non-unary-operator token in expression: {t}")
                     }
                 } else { panic!() };
@@ -171,6 +313,26 @@ impl Node {
                     t
                 }
             },
+            NodeT::Cast => {
+                let expr = match self.children.pop().unwrap() {
+                    Elem::Node(n)  => {
+                        assert!(n.t.is_evaluable());
+                        LElem::Node(n.extract())
+                    },
+                    Elem::Token(t) => LElem::Token(LToken::from(t))
+                };
+                let expr = Box::new(expr);
+
+                let t = if let Elem::Token(t) = self.children.pop().unwrap() {
+                    if let TokenData::TYPE(t) = t.data { t } 
+                    else { panic!("Found a non-type token in conversion: {}", t.data) }
+                } else { panic!() };
+
+                NodeData::Cast{
+                    t,
+                    expr
+                }
+            },
             NodeT::Function => {
                 let mut i = 0;
 
@@ -218,10 +380,12 @@ impl Node {
                 let v = self.children
                     .into_iter()
                     .map(|elem| {
-                        if let Elem::Node(stmt) = elem {
-                            let elem = LElem::Node(stmt.extract());
-                            Box::new(elem)
-                        } else { panic!() }
+                        // Expression statements may be bare tokens
+                        let elem = match elem {
+                            Elem::Node(stmt) => LElem::Node(stmt.extract()),
+                            Elem::Token(t)   => LElem::Token(LToken::from(t))
+                        };
+                        Box::new(elem)
                     })
                     .collect();
                 NodeData::Block{ v }
@@ -0,0 +1,99 @@
+use super::*;
+use type_check::TypeError;
+
+/// Control flow analysis. Ensures every path through a non-void function ends on a 'return'.
+pub fn check_returns (nodes: &[LNode]) -> Result<(), TypeError> {
+    for node in nodes {
+        if let NodeData::Function { ident, r_type, block, .. } = &node.data {
+            if *r_type != Type::Void && !block.returns() {
+                // Point at the closing brace
+                return Err( TypeError::msg(
+                    block.end() - 1,
+                    format!("Function '{ident}' may reach its end without returning a value of type {r_type}")
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+impl LElem {
+    /// Whether every path through the element ends on a 'return'.
+    pub(super) fn returns (&self) -> bool {
+        match self {
+            LElem::Node(n)  => n.returns(),
+            LElem::Token(_) => false,
+        }
+    }
+
+    fn breaks (&self) -> bool {
+        match self {
+            LElem::Node(n)  => n.breaks(),
+            LElem::Token(_) => false,
+        }
+    }
+}
+
+impl LNode {
+    /// Whether every path through the node ends on a 'return'.
+    /// - A block returns if any of its statements does. Following ones are unreachable
+    /// - An if returns if both branches do. Without an 'else' it might not
+    /// - A 'while true' loop without a 'break' never exits
+    /// - Expressions return if any term does, as terms are always evaluated
+    fn returns (&self) -> bool {
+        match &self.data {
+            NodeData::Return { .. } => true,
+            NodeData::Block { v } => v.iter().any(|stmt| stmt.returns()),
+            NodeData::If { expr, block, else_block } => 
+                expr.returns() || else_block.as_ref().is_some_and(|e| block.returns() && e.returns()),
+            NodeData::While { expr, block } => match expr.as_ref() {
+                LElem::Token(LToken { data: TokenData::BOOL(true), .. }) => !block.breaks(),
+                expr => expr.returns(),
+            },
+            NodeData::For { from, to, step, .. } => 
+                from.returns() || to.returns() || step.as_ref().is_some_and(|s| s.returns()),
+            NodeData::Declaration { expr, .. } |
+            NodeData::Assignment { expr, .. } |
+            NodeData::Cast { expr, .. } |
+            NodeData::UExpr { t: expr, .. } => expr.returns(),
+            NodeData::Expr { t1, t2, .. } => t1.returns() || t2.returns(),
+            NodeData::Invoke { args, .. } => args.iter().any(|arg| arg.returns()),
+            _ => false,
+        }
+    }
+
+    /// Whether a 'break' exits the current loop. Nested loops are not searched, their breaks exit them.
+    fn breaks (&self) -> bool {
+        match &self.data {
+            NodeData::Break => true,
+            NodeData::While { .. } | NodeData::For { .. } => false,
+            _ => self.get_children().iter().any(|c| c.breaks()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::super::passes;
+
+    #[test]
+    fn return_paths () {
+        // All branches return
+        assert!(passes("func f (n: i64) -> i64 { if n > 0 { return 1 } else { return 2 } }"));
+        // Missing 'else'
+        assert!(!passes("func f (n: i64) -> i64 { if n > 0 { return 1 } }"));
+        // Infinite loop, only left through 'return'. Nested loops may break
+        assert!(passes("func f () -> i64 { while true { while true { break } return 1 } }"));
+        assert!(!passes("func f () -> i64 { while true { if true { break } return 1 } }"));
+        // Bare return in void functions only
+        assert!(passes("func f () -> void { return }"));
+        assert!(!passes("func f () -> i64 { return }"));
+        // A bare return ends at the line break, rather than returning the next statement
+        assert!(passes("func f () -> void {\n return\n 1\n}"));
+        assert!(!passes("func f () -> i64 {\n return\n 1\n}"));
+        // Top level is not a function
+        assert!(!passes("return"));
+        assert!(!passes("let x = 1\nif x > 0 { return x }"));
+    }
+}
@@ -1,10 +1,10 @@
-use std::collections::{LinkedList, HashSet};
+use std::collections::{HashMap, HashSet};
 
 use super::*;
 
 pub struct ScopeError {
-    msg: String,
-    start: usize
+    pub msg: String,
+    pub start: usize
 }
 
 
@@ -12,177 +12,260 @@ pub struct ScopeError {
 
 #[derive(Debug)]
 struct SymbolStack {
-    stack: LinkedList<HashSet<String>>,
-    scope: usize
+    /// Scopes, innermost last. Maps identifiers to the ID of their declaration.
+    stack: Vec<HashMap<String, usize>>,
+    /// Function identifiers. Functions only exist globally, and are not annotated.
+    funcs: HashSet<String>,
+    /// Last assigned declaration ID
+    last_id: usize,
 }
 impl SymbolStack {
     fn new () -> Self {
-        let mut stack = LinkedList::new();
-        stack.push_back(HashSet::new());
-
         Self {
-            stack,
-            scope: 0,
+            stack: vec![HashMap::new()],
+            funcs: HashSet::new(),
+            last_id: 0,
         }
     }
 
-    fn get_scope (&self, ident: &String) -> Option<usize> {
-        println!("trying to find {} in:\n{:?}", ident, self.stack);
-        let mut level = self.scope;
-        for set in self.stack.iter() {
-            if set.contains(ident) {
-                return Some(level)
-            }
-            if level != 0 { 
-                level -= 1;
-            }
+    /// Resolve an identifier to its innermost declaration. Sets its ID.
+    /// Returns `Err()` if no declaration is found.
+    fn resolve (&self, sym: &mut Symbol) -> Result<(), String> {
+        if let Some(id) = self.stack.iter().rev().find_map(|scope| scope.get(&sym.name)) {
+            sym.id = *id;
+            Ok(())
+        } else {
+            Err(format!("cannot find identifier '{}'", sym.name))
         }
-        None
     }
 
-    /// Declare an identifier. Adds identifier to current scope.
+    /// Declare an identifier. Adds identifier to current scope under a new ID, shadowing
+    /// declarations of enclosing scopes. Sets its ID.
     /// Returns `Err()` if the identifier is found in the scope (redeclaration).
-    fn declare (&mut self, ident: String) -> Result<usize, String> {
-        println!("defining '{ident}' in:\n{:?}", self.stack);
-        if self.stack
-            .front_mut()
-            .expect("Should always have global scope")
-            .insert(ident.clone()) {
-            Ok(self.scope)
-        } else {
-            Err(format!("Attempted to redeclare identifier '{ident}'"))
+    fn declare (&mut self, sym: &mut Symbol) -> Result<(), String> {
+        let scope = self.stack.last_mut().expect("Should always have global scope");
+        if scope.contains_key(&sym.name) {
+            return Err(format!("Attempted to redeclare identifier '{}'", sym.name))
         }
+        self.last_id += 1;
+        scope.insert(sym.name.clone(), self.last_id);
+        sym.id = self.last_id;
+        Ok(())
+    }
+
+    /// Declare a function identifier.
+    /// Returns `Err()` if the function exists already.
+    fn declare_f (&mut self, ident: String) -> Result<(), String> {
+        if self.funcs.contains(&ident) {
+            return Err(format!("Attempted to redeclare identifier '{ident}'"))
+        }
+        self.funcs.insert(ident);
+        Ok(())
+    }
+
+    fn is_global (&self) -> bool {
+        self.stack.len() == 1
     }
 
     fn new_scope (&mut self) {
-        self.stack.push_front(HashSet::new());
-        self.scope += 1;
+        self.stack.push(HashMap::new());
     }
 
     fn end_scope (&mut self) {
-        if self.stack.len() == 1 || self.scope == 0 {
+        if self.is_global() {
             panic!("tried removing global scope");
         }
-        self.stack.pop_front();
-        self.scope -= 1;
+        self.stack.pop();
     }
 }
 
-pub fn resolve_scope (nodes: &mut Vec<Node>) -> Result<(), ScopeError> {
+pub fn resolve_scope (nodes: &mut [LNode]) -> Result<(), ScopeError> {
     let mut stack = SymbolStack::new();
+
+    // Declare STD lib & top-level functions upfront, so they can be invoked before their definition.
+    for ident in stdlib::signatures().into_keys() {
+        stack.declare_f(ident).expect("STD lib functions should be unique");
+    }
+    for node in nodes.iter() {
+        if let NodeData::Function { ident, .. } = &node.data {
+            stack.declare_f(ident.clone())
+                .map_err(|msg| ScopeError {
+                    msg,
+                    start: node.start
+                })?;
+        }
+    }
+
     for node in nodes {
         node.resolve_scope(&mut stack)?;
     }
     Ok(())
 }
 
-impl Node {
-    /// Uses a scope table (Set Stack) to annotate scope onto each identifier.
+impl LElem {
+    fn resolve_scope (&mut self, stack: &mut SymbolStack) -> Result<(), ScopeError> {
+        match self {
+            LElem::Node(n)  => n.resolve_scope(stack),
+            LElem::Token(t) => t.resolve_scope(stack),
+        }
+    }
+}
+
+impl LNode {
+    /// Uses a scope table (Map Stack) to annotate declaration IDs onto each variable.
     /// Algorithm:
     /// - Recursive traversal
-    /// - If self is Node::Declare => Add identifier to scope
-    /// - If self is Node::Block   => Add new set to stack
-    /// - If child is Token::IDENT => Check if identifier exists & annotate scope level
+    /// - If self is a declaration => Resolve initializer, then add identifier to scope
+    /// - If self is a block       => Add new map to stack
+    /// - If child is an identifier => Check if identifier exists & annotate its ID
     fn resolve_scope (&mut self, stack: &mut SymbolStack) -> Result<(), ScopeError> {
-        
-        // If self is declaration, add to stack.
-        if self.t == NodeT::Declaration {
-            let ident = 
-                if let Elem::Token(tok) = &self.children[0] {
-                    if let TokenData::IDENT(ident) = &tok.data { ident }
-                    else { panic!() }
-                } else { panic!() };
-            
-            stack.declare(ident.clone())
-                .map_err(|msg| ScopeError {
-                    msg,
-                    start: self.start
-                })?;
-        } 
-
-        // If self is function, define function identifier. Don't add scope annotation
-        if self.t == NodeT::Function {
-            let ident = {
-                if let Elem::Token(tok) = &self.children[0] {
-                    if let TokenData::IDENT(ident) = &tok.data { ident }
-                    else { panic!() }
-                } else { panic!() }
-            };
-            let scope_level = stack.declare(ident.clone())
-                .map_err(|msg| ScopeError {
-                    msg,
-                    start: self.start
-                })?;            
-
-            if scope_level > 0 {
-                return Err( ScopeError { 
-                    msg: "Attempted to define function at non-global scope".to_owned(),
-                    start: self.start
-                })
-            }
-        }
+        let start = self.start;
+        let err = |msg| ScopeError { msg, start };
 
-        // If self is Params, define parameter identifiers.
-        if self.t == NodeT::Params {
-            for child in self.children.iter().step_by(2) {
-                let ident = 
-                    if let Elem::Token(tok) = child {
-                        if let TokenData::IDENT(ident) = &tok.data { ident }
-                        else { panic!() }
-                    } else { panic!() };
-
-                stack.declare(ident.clone())
-                    .map_err(|msg| ScopeError {
-                        msg,
-                        start: self.start
-                    })?;            
-            }
+        match &mut self.data {
+            NodeData::Declaration { ident, expr, .. } => {
+                // The initializer can't see the declared variable
+                expr.resolve_scope(stack)?;
+                stack.declare(ident).map_err(err)?;
+            },
+            NodeData::Assignment { ident, expr } => {
+                expr.resolve_scope(stack)?;
+                stack.resolve(ident).map_err(err)?;
+            },
+            // Parameters get a scope of their own, wrapping the function's block. The body still sees
+            // the global scope, resolving top-level variables declared before the function.
+            NodeData::Function { params, block, .. } => {
+                if !stack.is_global() {
+                    return Err(err("Attempted to define function at non-global scope".to_owned()))
+                }
+
+                stack.new_scope();
+                if let Some(params) = params {
+                    if let LElem::Node(LNode { data: NodeData::Params { v, .. }, .. }) = params.as_mut() {
+                        for (ident, _) in v {
+                            stack.declare(ident).map_err(err)?;
+                        }
+                    } else { panic!() }
+                }
+                block.resolve_scope(stack)?;
+                stack.end_scope();
+            },
+            NodeData::Block { v } => {
+                stack.new_scope();
+                for stmt in v {
+                    stmt.resolve_scope(stack)?;
+                }
+                stack.end_scope();
+            },
+            // The range is resolved in the enclosing scope. The loop variable is then declared in a
+            // scope of its own, which only wraps the loop body.
+            NodeData::For { ident, from, to, step, block } => {
+                from.resolve_scope(stack)?;
+                to.resolve_scope(stack)?;
+                if let Some(step) = step {
+                    step.resolve_scope(stack)?;
+                }
+
+                stack.new_scope();
+                stack.declare(ident).map_err(err)?;
+                block.resolve_scope(stack)?;
+                stack.end_scope();
+            },
+            // Function identifiers are only checked for existence.
+            NodeData::Invoke { ident, args } => {
+                if !stack.funcs.contains(ident) {
+                    return Err(err(format!("cannot find function '{ident}'")))
+                }
+                for arg in args {
+                    arg.resolve_scope(stack)?;
+                }
+            },
+            NodeData::If { expr, block, else_block } => {
+                expr.resolve_scope(stack)?;
+                block.resolve_scope(stack)?;
+                if let Some(else_block) = else_block {
+                    else_block.resolve_scope(stack)?;
+                }
+            },
+            NodeData::While { expr, block } => {
+                expr.resolve_scope(stack)?;
+                block.resolve_scope(stack)?;
+            },
+            NodeData::Expr { t1, t2, .. } |
+            NodeData::Index { expr: t1, index: t2 } => {
+                t1.resolve_scope(stack)?;
+                t2.resolve_scope(stack)?;
+            },
+            NodeData::IndexAssignment { expr, index, value } => {
+                expr.resolve_scope(stack)?;
+                index.resolve_scope(stack)?;
+                value.resolve_scope(stack)?;
+            },
+            NodeData::Array { v } => {
+                for elem in v {
+                    elem.resolve_scope(stack)?;
+                }
+            },
+            NodeData::UExpr { t: expr, .. } |
+            NodeData::Cast { expr, .. } => expr.resolve_scope(stack)?,
+            NodeData::Return { expr } => {
+                if let Some(expr) = expr {
+                    expr.resolve_scope(stack)?;
+                }
+            },
+            NodeData::Break |
+            NodeData::Continue => (),
+            data => panic!("Scope resolution unimplemented for {}", NodeT::from(&*data))
         }
+        Ok(())
+    }
+}
 
-        // If self is block, start scope
-        if self.t == NodeT::Block {
-            stack.new_scope();
+impl LToken {
+    /// Annotates the declaration ID onto an identifier token. Other tokens are left untouched.
+    fn resolve_scope (&mut self, stack: &SymbolStack) -> Result<(), ScopeError> {
+        if let Some(mut sym) = self.symbol() {
+            stack.resolve(&mut sym)
+                .map_err(|msg| ScopeError { msg, start: self.start })?;
+            self.id = sym.id;
         }
+        Ok(())
+    }
+}
 
-        for (i, child) in self.children.iter_mut().enumerate() {
-            match child {
-                Elem::Node(child)  => child.resolve_scope(stack)?,
-                Elem::Token(child) => {
-                    match &mut child.data 
-                    {
-                        TokenData::IDENT(ident) => 
-                        {
-                            // NOTE: Function Invocation Exempted. Since stdlib is not in the
-                            // symbol stack.
-                            // TODO: Add lib functions to symbol stack
-                            if self.t == NodeT::Invoke && i == 0 { continue };
-
-                            // NOTE: Function Definition Exempted. No scope annotation for function
-                            // identifier
-                            if self.t == NodeT::Function && i == 0 { continue };
-
-                            // Add scope annotation to end of identifier
-                            if let Some(scope_level) = stack.get_scope(&ident) {
-                                ident.push('@');
-                                ident.push_str(&scope_level.to_string())
-                            } else {
-                                return Err(ScopeError { 
-                                    msg: format!("cannot find identifier '{ident}'"),
-                                    start: self.start, 
-                                })
-                            }
-                        }
-                        _ => ()
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::{analyze, passes};
+
+    /// Declaration IDs of top-level declarations, and of those directly inside top-level blocks.
+    fn declaration_ids (nodes: &[LNode]) -> Vec<usize> {
+        let mut ids = vec![];
+        for node in nodes {
+            match &node.data {
+                NodeData::Declaration { ident, .. } => ids.push(ident.id),
+                NodeData::Block { v } => for stmt in v {
+                    if let LElem::Node(LNode { data: NodeData::Declaration { ident, .. }, .. }) = stmt.as_ref() {
+                        ids.push(ident.id);
                     }
-                }
+                },
+                _ => ()
             }
         }
+        ids
+    }
 
-        // If self is block, end started scope
-        if self.t == NodeT::Block {
-            stack.end_scope();
-        }
+    #[test]
+    fn sibling_scopes () {
+        let nodes = analyze("let x = 0\n{ let x = 1; print64(x) }\n{ let x = 2.5; print_f64(x) }")
+            .ok().expect("analysis failed");
+        let ids = declaration_ids(&nodes);
+        assert_eq!(ids.len(), 3);
+        assert!(ids[0] != ids[1] && ids[1] != ids[2] && ids[0] != ids[2]);
 
-        Ok(())
+        assert!(passes("let x = 1; { let x = true; { let x = 2.5 } }"));
+        assert!(!passes("let x = 1; let x = 2"));
+        assert!(!passes("{ let x = 1 }; print64(x)"));
     }
 }
@@ -8,15 +8,38 @@ impl Node {
     pub fn trim (mut self) -> Elem {
         match self.t {
             NodeT::Declaration => {
-                self.filter_tok(vec![K_LET, ASSIGN]) 
+                self.filter_tok(vec![K_LET, COLON, ASSIGN]) 
+                    .recurse()
+                    .cast()
+            },
+            NodeT::Assignment => {
+                self.filter_tok(vec![ASSIGN]) 
                     .recurse()
                     .cast()
             },
             NodeT::If => {
-                self.filter_tok(vec![K_IF])
+                self.filter_tok(vec![K_IF, K_ELSE])
+                    .recurse()
+                    .cast()
+            },
+            NodeT::While => {
+                self.filter_tok(vec![K_WHILE])
+                    .recurse()
+                    .cast()
+            },
+            NodeT::For => {
+                self.filter_tok(vec![K_FOR, K_IN, RANGE, K_STEP])
                     .recurse()
                     .cast()
             },
+            NodeT::Break => {
+                self.filter_tok(vec![K_BREAK])
+                    .cast()
+            },
+            NodeT::Continue => {
+                self.filter_tok(vec![K_CONTINUE])
+                    .cast()
+            },
             NodeT::Return => {
                 self.filter_tok(vec![K_RETURN])
                     .recurse()
@@ -36,6 +59,29 @@ impl Node {
 
                 self.cast()
             },
+            NodeT::Array => {
+                self = self
+                    .filter_tok(vec![BRACKET_L, BRACKET_R])
+                    .recurse();
+
+                // Consume child "Args", set its children as own.
+                if let Some(Elem::Node(args)) = self.children.pop() {
+                    assert!(args.t.is_args());
+                    self.children = args.children;
+                }
+
+                self.cast()
+            },
+            NodeT::Index => {
+                self.filter_tok(vec![BRACKET_L, BRACKET_R])
+                    .recurse()
+                    .cast()
+            },
+            NodeT::IndexAssignment => {
+                self.filter_tok(vec![BRACKET_L, BRACKET_R, ASSIGN])
+                    .recurse()
+                    .cast()
+            },
             NodeT::Args => {
                 self.filter_tok(vec![COMMA])
                     .recurse()
@@ -46,34 +92,42 @@ impl Node {
                 self.recurse()
                     .collapse_if_1()
             },
-            NodeT::T1 => {
+            // Every precedence level is the same logical node. Single-term levels collapse,
+            // leaving only the operator nodes. 
+            NodeT::T1 | NodeT::T2 | NodeT::T3 | NodeT::T4 | NodeT::T5 => {
                 self.change_t(NodeT::Expr)
                     .recurse()
                     .collapse_if_1()
             },
-            NodeT::T2 => {
-                self.change_t(NodeT::Expr)
+            // 'expr as T'. Reordered into the 'T(expr)' layout.
+            NodeT::T6 => {
+                self = self.filter_tok(vec![K_AS]);
+                self.children.reverse();
+                self.change_t(NodeT::Cast)
                     .recurse()
                     .collapse_if_1()
             },
-            NodeT::T3 => {
-                self.change_t(NodeT::Expr)
+            NodeT::T7 => {
+                let t = if self.children.len() == 2 { NodeT::UExpr } else { NodeT::Expr };
+                self.change_t(t)
                     .recurse()
+                    .fold_negative_literal()
                     .collapse_if_1()
             },
             NodeT::TBase => {
                 self = self.filter_tok(vec![PAREN_L, PAREN_R]);
-                let t = if self.children.len() == 2 { NodeT::UExpr } else { NodeT::Expr };
+                let t = if self.children.len() == 2 { NodeT::Cast } else { NodeT::Expr };
                 self.change_t(t)
                     .recurse()
                     .collapse_if_1()
             },
             NodeT::Statement => {
-                self.recurse()
+                self.filter_tok(vec![SEMI])
+                    .recurse()
                     .collapse_if_1()
             },
             NodeT::Function => {
-                self.filter_tok(vec![K_FUNC, PAREN_L, PAREN_R, ARROW])
+                self.filter_tok(vec![K_FUNC, PAREN_L, PAREN_R, ARROW, SEMI])
                     .recurse()
                     .cast()
             },
@@ -163,6 +217,26 @@ impl Node {
         self
     }
 
+    /// Folds a negated integer literal into a single negative literal token. Keeps the start
+    /// position of the '-' operator.
+    fn fold_negative_literal (mut self) -> Self {
+        if let [Elem::Token(op), Elem::Token(lit)] = &self.children[..] {
+            let data = match (&op.data, &lit.data) {
+                (TokenData::OP5_n(op), TokenData::INT(n)) if op == "-" => Some(TokenData::INT(-n)),
+                (TokenData::OP5_n(op), TokenData::FLOAT(n)) if op == "-" => Some(TokenData::FLOAT(format!("-{n}"))),
+                _ => None
+            };
+            if let Some(data) = data {
+                self.children = vec![Elem::Token(Token {
+                    start: self.start,
+                    end: lit.end,
+                    data
+                })];
+            }
+        }
+        self
+    }
+
     /// Convert to Elem::Node for chaining
     fn cast (self) -> Elem {
         Elem::Node(self)
@@ -179,3 +253,77 @@ impl Node {
 }
 
 
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::Parser;
+
+    /// Parses `let x = <expr>` and renders the expression fully parenthesized. 
+    fn parenthesize (expr: &str) -> String {
+        fn render (elem: &LElem) -> String {
+            match elem {
+                LElem::Token(t) => match &t.data {
+                    TokenData::INT(n)    => n.to_string(),
+                    TokenData::BOOL(v)   => v.to_string(),
+                    TokenData::IDENT(s)  => s.clone(),
+                    t => panic!("unexpected token {t}")
+                },
+                LElem::Node(n) => match &n.data {
+                    NodeData::Expr { t1, t2, op } => format!("({} {op} {})", render(t1), render(t2)),
+                    NodeData::UExpr { t, op }     => format!("({op}{})", render(t)),
+                    NodeData::Cast { t, expr }    => format!("({} as {t})", render(expr)),
+                    d => panic!("unexpected node {d}")
+                }
+            }
+        }
+
+        let source = format!("let x = {expr}");
+        let tokens = crate::lexer::tokenize(&source).ok().expect("tokenization failed");
+        let nodes  = Parser::new(tokens, &source).parse().ok().expect("parsing failed");
+
+        let node = if let Elem::Node(n) = nodes[0].clone().trim() { n.extract() } 
+            else { panic!() };
+        if let NodeData::Declaration { expr, .. } = &node.data {
+            render(expr)
+        } else { panic!("expected declaration, found {}", node.data) }
+    }
+
+    #[test]
+    fn left_associativity () {
+        assert_eq!(parenthesize("10 - 3 - 2"), "((10 - 3) - 2)");
+        assert_eq!(parenthesize("8 / 4 / 2"), "((8 / 4) / 2)");
+        assert_eq!(parenthesize("a && b && c"), "((a && b) && c)");
+        assert_eq!(parenthesize("a == b != c"), "((a == b) != c)");
+    }
+
+    #[test]
+    fn precedence () {
+        assert_eq!(parenthesize("1 + 2 * 3 - 4"), "((1 + (2 * 3)) - 4)");
+        assert_eq!(parenthesize("(1 + 2) * 3"), "((1 + 2) * 3)");
+        assert_eq!(parenthesize("a == b && c"), "((a == b) && c)");
+        assert_eq!(parenthesize("a || b && c"), "(a || (b && c))");
+        assert_eq!(parenthesize("a + 1 < b * 2 == c"), "(((a + 1) < (b * 2)) == c)");
+        assert_eq!(parenthesize("!a && b || c"), "(((!a) && b) || c)");
+        assert_eq!(parenthesize("-a * b % c"), "(((-a) * b) % c)");
+        assert_eq!(parenthesize("a - -b"), "(a - (-b))");
+    }
+
+    #[test]
+    fn negative_literals () {
+        assert_eq!(parenthesize("-5"), "-5");
+        assert_eq!(parenthesize("2 * -5"), "(2 * -5)");
+        assert_eq!(parenthesize("3 - -5"), "(3 - -5)");
+        assert_eq!(parenthesize("--5"), "5");
+        assert_eq!(parenthesize("-(1 + 2)"), "(-(1 + 2))");
+    }
+
+    #[test]
+    fn cast_precedence () {
+        assert_eq!(parenthesize("a * b as f64"), "(a * (b as f64))");
+        assert_eq!(parenthesize("-a as i32 as i64"), "(((-a) as i32) as i64)");
+        assert_eq!(parenthesize("a as u8 + 1 < b"), "(((a as u8) + 1) < b)");
+        assert_eq!(parenthesize("u8(a) as i64"), "((a as u8) as i64)");
+    }
+}
@@ -1,5 +1,5 @@
 use super::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug)]
 pub struct TypeError {
@@ -13,7 +13,7 @@ impl TypeError {
     fn new (start: usize, msg: String, expected: Type, found: Type) -> Self {
         Self { start, msg, expected: Some(expected), found: Some(found) }
     }
-    fn msg (start: usize, msg: String) -> Self {
+    pub(super) fn msg (start: usize, msg: String) -> Self {
         Self { start, msg, expected: None, found: None }
     }
 }
@@ -32,34 +32,46 @@ impl LElem {
 pub type FuncSig = (Vec<Type>, Type);
 #[derive(Debug, Default)]
 pub struct TypeTable {
-    pub vars: HashMap<String, Type>,
-    pub funcs: HashMap<String, FuncSig>
+    /// Variable types, by declaration ID
+    pub vars: HashMap<usize, Type>,
+    /// Function signatures. STD lib functions may be overloaded
+    pub funcs: HashMap<String, Vec<FuncSig>>,
+    /// Declaration IDs of variables declared with 'let mut'
+    pub muts: HashSet<usize>,
+    /// Function whose body is being checked, for 'return' statements
+    pub(crate) func: Option<String>,
+    /// Number of loops around the statement being checked, for 'break' & 'continue'
+    pub(crate) loop_depth: usize,
 }
 impl TypeTable {
     fn define_f (&mut self, ident: &String, t: (Vec<Type>, Type)) {
-        if self.funcs.insert(ident.clone(), t).is_some() {
+        if self.funcs.insert(ident.clone(), vec![t]).is_some() {
             panic!("overwriting of function type: {}", ident);
         }
     }
-    fn define_v (&mut self, ident: &String, t: Type) {
-        println!("defining variable '{ident}'");
-        if self.vars.insert(ident.clone(), t).is_some() {
-            panic!("overwriting of variable type: {}", ident);
+    fn define_v (&mut self, sym: &Symbol, t: Type) {
+        println!("defining variable '{sym}'");
+        if self.vars.insert(sym.id, t).is_some() {
+            panic!("overwriting of variable type: {}", sym);
         }
     }
 
-    fn get_f (&self, ident: &String) -> &FuncSig {
+    fn define_mut (&mut self, sym: &Symbol) {
+        self.muts.insert(sym.id);
+    }
+
+    fn get_f (&self, ident: &String) -> &[FuncSig] {
         if let Some(t) = self.funcs.get(ident) {
             t
         } else {
             panic!("function type not found for '{}'. Should've been caught in scope annotations", ident);
         }
     }
-    fn get_v (&self, ident: &String) -> &Type {
-        if let Some(t) = self.vars.get(ident) {
+    fn get_v (&self, sym: &Symbol) -> &Type {
+        if let Some(t) = self.vars.get(&sym.id) {
             t
         } else {
-            panic!("variable type not found for '{}'. Should've been caught in scope annotations", ident);
+            panic!("variable type not found for '{}'. Should've been caught in scope annotations", sym);
         }    
     }
 }
@@ -68,6 +80,20 @@ impl TypeTable {
 pub fn type_check (nodes: Vec<LNode>) -> Result<Vec<LNode>, TypeError> { 
     let mut table = TypeTable::with_std();
 
+    // Collect top-level function signatures before checking bodies. Allows forward references.
+    for node in nodes.iter() {
+        if let NodeData::Function { ident, params, r_type, .. } = &node.data {
+            let param_t = 
+                if let Some(params) = params {
+                    if let NodeData::Params { v, .. } = &params.node_data() {
+                        v.iter().map(|(_, t)| t.clone()).collect()
+                    } else { panic!() }
+                } else { vec![] };
+
+            table.define_f(ident, (param_t, r_type.clone()));
+        }
+    }
+
     nodes
         .into_iter()
         .map(|node| 
@@ -92,7 +118,146 @@ impl LElem {
     }
 }
 
-static mut CURRENT_FUNC: Option<String> = None;
+impl LElem {
+    /// Types an integer literal as the numeric type `t`, if the value fits in it. Array literals
+    /// are coerced element-wise. Generic invocations, e.g. 'list()', bind their type variable to `t`.
+    /// Returns whether the literal was coerced.
+    fn coerce_literal (&mut self, t: &Type) -> bool {
+        if let LElem::Node(LNode { data: NodeData::Invoke { .. }, t: inv_t, .. }) = self {
+            let fits = inv_t.is_generic() && inv_t.matches(t, &mut None);
+            if fits {
+                *inv_t = t.clone();
+            }
+            return fits
+        }
+        if let (LElem::Node(LNode { data: NodeData::Array { v }, t: arr_t, .. }), Type::Array(elem_t)) = (&mut *self, t) {
+            let fits = v.iter().all(|elem| elem.clone().coerce_literal(elem_t));
+            if fits {
+                v.iter_mut().for_each(|elem| { elem.coerce_literal(elem_t); });
+                *arr_t = t.clone();
+            }
+            return fits
+        }
+        if let LElem::Token(LToken { data: TokenData::INT(n), t: lit_t, .. }) = self {
+            let fits = match t {
+                Type::Int | Type::Float => true,
+                Type::I32 => i32::try_from(*n).is_ok(),
+                Type::U8  => u8::try_from(*n).is_ok(),
+                Type::U64 => *n >= 0,
+                _ => false
+            };
+            if fits {
+                *lit_t = t.clone();
+            }
+            return fits
+        }
+        false
+    }
+}
+
+impl Type {
+    /// Whether a term of type `t` can be passed as `self`. Binds the type variable of generic
+    /// signatures on its first occurrence, then checks against it.
+    fn matches (&self, t: &Type, binding: &mut Option<Type>) -> bool {
+        match (self, t) {
+            (Type::Generic, t) => binding.get_or_insert_with(|| t.clone()) == t,
+            (Type::Array(a), Type::Array(b)) |
+            (Type::List(a), Type::List(b)) => a.matches(b, binding),
+            (a, b) => a == b,
+        }
+    }
+
+    /// Substitutes the bound type variable.
+    fn bind (&self, binding: &Option<Type>) -> Type {
+        match self {
+            Type::Generic  => binding.clone().unwrap_or(Type::Generic),
+            Type::Array(t) => Type::Array(Box::new(t.bind(binding))),
+            Type::List(t)  => Type::List(Box::new(t.bind(binding))),
+            t => t.clone(),
+        }
+    }
+
+    /// Whether the type contains an unbound type variable.
+    fn is_generic (&self) -> bool {
+        match self {
+            Type::Generic => true,
+            Type::Array(t) | Type::List(t) => t.is_generic(),
+            _ => false,
+        }
+    }
+}
+
+/// Picks the first signature accepting arguments of types `args`.
+pub fn select_overload<'a> (sigs: &'a [FuncSig], args: &[Type]) -> Option<&'a FuncSig> {
+    sigs.iter().find(|(params, _)| {
+        let mut binding = None;
+        params.len() == args.len() && params.iter().zip(args).all(|(p, t)| p.matches(t, &mut binding))
+    })
+}
+
+impl LNode {
+    /// Checks typed arguments against a signature. Integer literals & generic invocations take on
+    /// the parameter types. Returns the arguments & the return type.
+    fn check_args (start: usize, sig: &FuncSig, args: Vec<(LElem, Type)>) -> Result<(Vec<ChildRef>, Type), TypeError> {
+        if args.len() != sig.0.len() {
+            return Err( TypeError::msg(
+                start,
+                format!("Argument lengths mismatched. expected {}, found {}", sig.0.len(), args.len())
+            ))
+        }
+
+        // Generic signatures bind 'T' to the first matching argument
+        let mut binding = None;
+        let mut out = Vec::with_capacity(args.len());
+        for (i, ((mut arg, mut t), param)) in args.into_iter().zip(&sig.0).enumerate() {
+            let expected = param.bind(&binding);
+            if !expected.is_generic() && arg.coerce_literal(&expected) {
+                t = expected;
+            }
+            if !param.matches(&t, &mut binding) {
+                return Err( TypeError::new(
+                    start,
+                    format!("Argument no.{i} has mismatched type."),
+                    param.bind(&binding),
+                    t
+                ))
+            }
+            if t.is_generic() {
+                return Err( TypeError::msg(
+                    arg.start(),
+                    format!("Type {t} of the argument cannot be inferred")
+                ))
+            }
+            out.push(Box::new(arg));
+        }
+
+        // Return function signature. Left generic if 'T' only appears in it
+        Ok((out, sig.1.bind(&binding)))
+    }
+
+    /// Checks 'expr[index]'. Returns the checked terms & the element type.
+    fn check_indexing (table: &mut TypeTable, expr: LElem, index: LElem) -> Result<((LElem, LElem), Type), TypeError> {
+        let (expr, t) = expr.type_check(table)?;
+        let Type::Array(elem_t) = t else {
+            return Err( TypeError::msg(
+                expr.start(),
+                format!("Cannot index into a term of type {t}")
+            ))
+        };
+
+        let (index, index_t) = index.type_check(table)?;
+        if index_t != Type::Int {
+            return Err( TypeError::new(
+                index.start(),
+                "Array index does not evaluate to an integer".to_owned(),
+                Type::Int,
+                index_t
+            ));
+        }
+        Ok(((expr, index), *elem_t))
+    }
+}
+
 impl LNode {
     /// Uses a type table to ensure type correctness of program.
     /// Does not need to bother with scoping issues. Resolved already.
@@ -111,11 +276,19 @@ impl LNode {
                     ));
                 }
 
-                let t = match (t1_t, op.as_str(), t2_t) {
-                    (_, "==" | "!=",_) => Type::Bool,
+                let t = match (&t1_t, op.as_str(), &t2_t) {
+                    // Arrays & lists are pointers, and are not compared by content
+                    (t, "==" | "!=",_) if !matches!(t, Type::Array(_) | Type::List(_) | Type::Void) => Type::Bool,
+                    (t, "<" | "<=" | ">" | ">=", _) if t.is_int() || *t == Type::Float => Type::Bool,
+                    (Type::String, "<" | "<=" | ">" | ">=", Type::String) => Type::Bool,
                     (Type::Bool, "||" | "&&"  ,Type::Bool) => Type::Bool,
-                    (Type::Int, "*" | "+" | "-" | "/", Type::Int) => Type::Int,
-                    (t1 @ _, op @ _, t2 @ _) => panic!("invalid operands. Cannot apply operator '{op}' on terms {t1} and {t2}")
+                    (t, "*" | "+" | "-" | "/" | "%", _) if t.is_int() => t.clone(),
+                    (Type::Float, "*" | "+" | "-" | "/", _) => Type::Float,
+                    (Type::String, "+", Type::String) => Type::String,
+                    (t, op, _) => return Err( TypeError::msg(
+                        t1.start(),
+                        format!("Cannot apply operator '{op}' on terms of type {t}")
+                    ))
                 };
 
                 (
@@ -127,12 +300,44 @@ impl LNode {
                 t 
                 )
             },
+            NodeData::Cast { t, expr } => {
+                let (expr, from) = expr.type_check(table)?;
+
+                // Numbers convert between each other. Booleans convert to & from integers.
+                // Void has no value, and generics no representation, to convert.
+                let numeric = |t: &Type| t.is_int() || *t == Type::Float;
+                let castable = |t: &Type| *t != Type::Void && !t.is_generic();
+                let allowed = castable(&from) && castable(&t) && (
+                    from == t
+                    || numeric(&from) && numeric(&t)
+                    || from == Type::Bool && t.is_int()
+                    || from.is_int() && t == Type::Bool
+                );
+                if !allowed {
+                    return Err( TypeError::msg(
+                        self.start,
+                        format!("Cannot cast a term of type {from} into {t}")
+                    ))
+                }
+
+                (
+                NodeData::Cast{
+                    t: t.clone(),
+                    expr: Box::new(expr),
+                },
+                t
+                )
+            },
             NodeData::UExpr { t: term, op } => {
                 let (term, t) = term.type_check(table)?;
                 
                 let t = match (t, op.as_str()) {
                     (Type::Bool, "!") => Type::Bool,
-                    (t @ _, op @ _) => panic!("invalid operands. Cannot apply unary operator '{op}' on term {t}")
+                    (t, "-") if t.is_signed() || t == Type::Float => t,
+                    (t, op) => return Err( TypeError::msg(
+                        self.start,
+                        format!("Cannot apply unary operator '{op}' on term of type {t}")
+                    ))
                 };
 
                 (
@@ -143,7 +348,7 @@ impl LNode {
                 t 
                 )
             },
-            NodeData::If { expr, block } => {
+            NodeData::If { expr, block, else_block } => {
                 let (expr, t) = expr.type_check(table)?;
                 
                 // Expression type must be boolean
@@ -155,128 +360,350 @@ impl LNode {
                         t 
                     ));
                 }
-                let (block, _) = block.type_check(table)?;
+                let (block, block_t) = block.type_check(table)?;
                 let block = Box::new(block);
+
+                // Evaluates to a value only if both branches evaluate to one of the same type
+                let (else_block, t) = if let Some(else_block) = else_block {
+                    let (else_block, else_t) = else_block.type_check(table)?;
+                    let t = match (block_t, else_t) {
+                        (a, b) if a == b => a,
+                        (Type::Void, _) | (_, Type::Void) => Type::Void,
+                        (a, b) => return Err( TypeError::new(
+                            else_block.start(),
+                            "If branches evaluate to different types".to_owned(),
+                            a,
+                            b
+                        ))
+                    };
+                    (Some(Box::new(else_block)), t)
+                } else { (None, Type::Void) };
                 
                 (
                 NodeData::If { 
                     expr: Box::new(expr),
-                    block
+                    block,
+                    else_block
                 },
                 t
                 )
             }
-            NodeData::Return { expr } => {
+            NodeData::While { expr, block } => {
                 let (expr, t) = expr.type_check(table)?;
+                
+                // Expression type must be boolean
+                if t != Type::Bool {
+                    return Err( TypeError::new(
+                        expr.start(),
+                        "While loop expression does not evaluate to a boolean".to_owned(),
+                        Type::Bool,
+                        t 
+                    ));
+                }
 
-                // Check type with function signature
-                unsafe { 
-                    let ident = CURRENT_FUNC.clone()
-                        .expect("current func undefined, yet a 'return' statement found. Should've been caught on scope checking.");
-
-                    let out_t = &table.get_f(&ident).1;
-                    if *out_t != t {
+                // Track loop nesting for 'break' & 'continue'
+                table.loop_depth += 1;
+                let block = block.type_check(table);
+                table.loop_depth -= 1;
+                let (block, _) = block?;
+                
+                (
+                NodeData::While { 
+                    expr: Box::new(expr),
+                    block: Box::new(block)
+                },
+                Type::Void
+                )
+            }
+            NodeData::For { ident, from, to, step, block } => {
+                // Range bounds & step must be integers
+                let mut check_bound = |elem: ChildRef| -> Result<ChildRef, TypeError> {
+                    let (elem, t) = elem.type_check(table)?;
+                    if t != Type::Int {
                         return Err( TypeError::new(
-                            expr.start(),
-                            "Return expression does not match function signature".to_owned(),
-                            out_t.clone(),
-                            t 
+                            elem.start(),
+                            "For loop range does not evaluate to an integer".to_owned(),
+                            Type::Int,
+                            t
                         ));
                     }
-                }
+                    Ok(Box::new(elem))
+                };
+                let from = check_bound(from)?;
+                let to   = check_bound(to)?;
+                let step = step.map(check_bound).transpose()?;
+
+                // Loop variable
+                table.define_v(&ident, Type::Int);
+
+                // Track loop nesting for 'break' & 'continue'
+                table.loop_depth += 1;
+                let block = block.type_check(table);
+                table.loop_depth -= 1;
+                let (block, _) = block?;
+
                 (
-                NodeData::Return { 
-                    expr: Box::new(expr)
+                NodeData::For { 
+                    ident,
+                    from,
+                    to,
+                    step,
+                    block: Box::new(block)
                 },
-                t
+                Type::Void
                 )
             }
-            NodeData::Invoke { ident, args } => {
-
-                // Check Args
-                let sig = table.get_f(&ident);
-
-                // Check arg length
-                if args.len() != sig.0.len() {
+            data @ (NodeData::Break | NodeData::Continue) => {
+                if table.loop_depth == 0 {
                     return Err( TypeError::msg(
                         self.start,
-                        format!("Argument lengths mismatched. expected {}, found {}", sig.0.len(), args.len())
-                    ))
+                        format!("{} statement found outside of a loop", NodeT::from(&data))
+                    ));
                 }
+                (data, Type::Void)
+            }
+            NodeData::Return { expr } => {
+                let Some(ident) = table.func.clone() else {
+                    return Err( TypeError::msg(
+                        self.start,
+                        "Return statement found outside of a function".to_owned()
+                    ));
+                };
+                let out_t = table.get_f(&ident)[0].1.clone();
 
-                let args = args.into_iter().enumerate().map(|(i, arg)| {
-                    let (arg, t) = arg.type_check(table)?; 
-                    let sig = table.get_f(&ident);
-                    if t != sig.0[i] {
-                        return Err( TypeError::new(
-                            self.start,
-                            format!("Argument no.{i} has mismatched type."),
-                            sig.0[i].clone(),
+                // A bare 'return' returns void
+                let (expr, start, t) = if let Some(expr) = expr {
+                    let (mut expr, mut t) = expr.type_check(table)?;
+                    if expr.coerce_literal(&out_t) {
+                        t = out_t.clone();
+                    }
+                    let start = expr.start();
+                    (Some(Box::new(expr)), start, t)
+                } else { (None, self.start, Type::Void) };
+
+                // Check type with function signature
+                if out_t != t {
+                    return Err( TypeError::new(
+                        start,
+                        "Return expression does not match function signature".to_owned(),
+                        out_t.clone(),
+                        t 
+                    ));
+                }
+                (
+                NodeData::Return { expr },
+                Type::Void
+                )
+            }
+            NodeData::Array { v } => {
+                // Elements must all be of the same type
+                let mut elem_t: Option<Type> = None;
+                let v = v.into_iter().map(|elem| {
+                    let (elem, t) = elem.type_check(table)?;
+                    match &elem_t {
+                        _ if t == Type::Void => return Err( TypeError::msg(
+                            elem.start(),
+                            "Array element does not evaluate to a value".to_owned()
+                        )),
+                        Some(first) if *first != t => return Err( TypeError::new(
+                            elem.start(),
+                            "Array elements not of same type".to_owned(),
+                            first.clone(),
                             t
-                        ))
+                        )),
+                        _ => elem_t = Some(t)
                     }
-                    Ok(Box::new(arg))
+                    Ok(Box::new(elem))
                 }).collect::<Result<_, _>>()?;
 
-                // Return function signature
-                let sig = table.get_f(&ident);
+                (
+                NodeData::Array { v },
+                Type::Array(Box::new(elem_t.expect("array literals have at least one element")))
+                )
+            }
+            NodeData::Index { expr, index } => {
+                let ((expr, index), elem_t) = Self::check_indexing(table, *expr, *index)?;
+                (
+                NodeData::Index { 
+                    expr: Box::new(expr),
+                    index: Box::new(index),
+                },
+                elem_t
+                )
+            }
+            NodeData::IndexAssignment { expr, index, value } => {
+                let ((expr, index), elem_t) = Self::check_indexing(table, *expr, *index)?;
+
+                // Elements of a variable, e.g. 'a[0]' or 'a[0][1]', are only assigned through 'let mut'
+                let mut base = &expr;
+                while let LElem::Node(LNode { data: NodeData::Index { expr, .. }, .. }) = base {
+                    base = expr;
+                }
+                let var = match base {
+                    LElem::Token(t) => t.symbol(),
+                    LElem::Node(_) => None
+                };
+                if let Some(sym) = var.filter(|sym| !table.muts.contains(&sym.id)) {
+                    return Err( TypeError::msg(
+                        self.start,
+                        format!("Cannot assign to element of immutable variable '{}'. Declare it with 'let mut'", sym.name)
+                    ));
+                }
+
+                // Assigned value must match the element type
+                let (mut value, mut value_t) = value.type_check(table)?;
+                if value.coerce_literal(&elem_t) {
+                    value_t = elem_t.clone();
+                }
+                if value_t != elem_t {
+                    return Err( TypeError::new(
+                        value.start(),
+                        "Assigned expression does not match element type".to_owned(),
+                        elem_t,
+                        value_t
+                    ));
+                }
+
+                (
+                NodeData::IndexAssignment { 
+                    expr: Box::new(expr),
+                    index: Box::new(index),
+                    value: Box::new(value),
+                },
+                Type::Void
+                )
+            }
+            NodeData::Invoke { ident, args } => {
+
+                // Check Args
+                let args = args.into_iter()
+                    .map(|arg| arg.type_check(table))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                // Overloaded functions take the first matching signature. Mismatches are reported
+                // against the first one.
+                let sigs = table.get_f(&ident);
+                let sig = sigs.iter()
+                    .find(|sig| Self::check_args(self.start, sig, args.clone()).is_ok())
+                    .unwrap_or(&sigs[0])
+                    .clone();
+                let (args, t) = Self::check_args(self.start, &sig, args)?;
 
                 (
                 NodeData::Invoke { ident, args },
-                sig.1.clone()
+                t
                 )
             }
             NodeData::Block { v } => {
-                // TODO: Last one determines type.
+                // A trailing expression is the value of the block
                 let mut nv = Vec::new();
                 nv.reserve(v.len());
+                let mut t = Type::Void;
                 for stmt in v {
-                    let (stmt, _) = stmt.type_check(table)?;
+                    let (stmt, stmt_t) = stmt.type_check(table)?;
+                    t = if stmt.is_evaluable() { stmt_t } else { Type::Void };
                     nv.push(Box::new(stmt));
                 }
                 (
                 NodeData::Block{ v: nv },
-                Type::Void
+                t
                 )
             }
-            NodeData::Declaration { ident, expr } => {
+            NodeData::Declaration { ident, mutable, t, expr } => {
+                let (mut expr, mut expr_t) = expr.type_check(table)?;
+                if expr_t == Type::Void {
+                    return Err( TypeError::msg(
+                        expr.start(),
+                        "Initializer does not evaluate to a value".to_owned()
+                    ));
+                }
+
+                // Initializer must match the annotation. Integer literals adopt the annotated type
+                if let Some(t) = &t {
+                    if expr.coerce_literal(t) {
+                        expr_t = t.clone();
+                    }
+                    if *t != expr_t {
+                        return Err( TypeError::new(
+                            expr.start(),
+                            "Initializer does not match the declared type".to_owned(),
+                            t.clone(),
+                            expr_t
+                        ));
+                    }
+                }
+                if expr_t.is_generic() {
+                    return Err( TypeError::msg(
+                        expr.start(),
+                        format!("Type {expr_t} of the initializer cannot be inferred. Annotate the declaration")
+                    ));
+                }
+
                 // Set type for ident
-                let (expr, expr_t) = expr.type_check(table)?;
                 table.define_v(&ident, expr_t.clone());
+                if mutable {
+                    table.define_mut(&ident);
+                }
                 let expr = Box::new(expr);
 
                 (
                 NodeData::Declaration { 
                     ident,
+                    mutable,
+                    t,
                     expr,
                 },
                 Type::Void
                 )
             },
+            NodeData::Assignment { ident, expr } => {
+                if !table.muts.contains(&ident.id) {
+                    return Err( TypeError::msg(
+                        self.start,
+                        format!("Cannot assign to immutable variable '{}'. Declare it with 'let mut'", ident.name)
+                    ));
+                }
+
+                // Assigned value must match the variable's type
+                let (mut expr, mut expr_t) = expr.type_check(table)?;
+                let var_t = table.get_v(&ident);
+                if expr.coerce_literal(var_t) {
+                    expr_t = var_t.clone();
+                }
+                if *var_t != expr_t {
+                    return Err( TypeError::new(
+                        expr.start(),
+                        "Assigned expression does not match variable type".to_owned(),
+                        var_t.clone(),
+                        expr_t
+                    ));
+                }
+
+                (
+                NodeData::Assignment { 
+                    ident,
+                    expr: Box::new(expr),
+                },
+                Type::Void
+                )
+            },
             NodeData::Function { ident, params, r_type, block } => {
                 
-                unsafe {
-                    CURRENT_FUNC = Some(ident.clone());
-                }
+                table.func = Some(ident.clone());
 
-                // Extract Parameter Types, Add to signature and type table.
-                let param_t = 
-                    if let Some(ref params) = params {
-                        if let NodeData::Params { v } = &params.node_data() {
-                            v.iter().map(|(ident, t)| {
-                                table.define_v(ident, t.clone());
-                                t.clone()
-                            }).collect()
-                        } else { panic!() }
-                    } else { vec![] };
-
-                // Set signature
-                let sig = (param_t, r_type.clone());
-                table.define_f(&ident, sig);
+                // Add parameter types to type table. Signature was collected upfront.
+                if let Some(ref params) = params {
+                    if let NodeData::Params { v, .. } = &params.node_data() {
+                        for (ident, t) in v {
+                            table.define_v(ident, t.clone());
+                        }
+                    } else { panic!() }
+                }
 
                 // Recurse into block
                 let (block, _) = block.type_check(table)?;
                 let block = Box::new(block);
+                table.func = None;
 
                 (
                 NodeData::Function { 
@@ -306,9 +733,10 @@ impl LToken {
     fn type_check (self, table: &mut TypeTable) -> (LToken, Type) {
         let t = match &self.data {
             TokenData::INT(_)       => Type::Int,
+            TokenData::FLOAT(_)     => Type::Float,
             TokenData::BOOL(_)      => Type::Bool,
             TokenData::STR_LIT(_)   => Type::String,
-            TokenData::IDENT(ident) => table.get_v(ident).clone(),
+            TokenData::IDENT(_)     => table.get_v(&self.symbol().unwrap()).clone(),
             data @ _ => panic!("Typing unimplemented for token {}", TokenT::from(data))
         };
         (
@@ -320,3 +748,130 @@ impl LToken {
         )
     }
 }
+
+
+#[cfg(test)]
+mod test {
+    use super::super::passes;
+
+    #[test]
+    fn forward_references () {
+        assert!(passes("print64(f(1))\nfunc f (n: i64) -> i64 { return g(n) }\nfunc g (n: i64) -> i64 { return n }"));
+        assert!(passes("func even (n: i64) -> bool { if n == 0 { return true }; return odd(n - 1) }\n\
+            func odd (n: i64) -> bool { if n == 0 { return false }; return even(n - 1) }"));
+        assert!(!passes("print64(f(true))\nfunc f (n: i64) -> i64 { return n }"));
+        assert!(!passes("print64(h(1))\nfunc f (n: i64) -> i64 { return n }"));
+    }
+
+    #[test]
+    fn globals () {
+        assert!(passes("let mut n = 0\nfunc f () -> void { n += 1 }"));
+        assert!(!passes("let n = 0\nfunc f () -> void { n += 1 }"));
+        // Functions can't see locals of other functions, or globals declared after them
+        assert!(!passes("func f () -> void { let n = 1 }\nfunc g () -> i64 { return n }"));
+        assert!(!passes("func f () -> i64 { return n }\nlet n = 0"));
+    }
+
+    #[test]
+    fn block_values () {
+        assert!(passes("let c = true; let y: i64 = if c { 1 } else { 2 }; let z = { let a = 2; a * 3 } + y"));
+        assert!(passes("let s: str = if true { \"a\" } else if false { \"b\" } else { \"c\" }"));
+        assert!(!passes("let y = if true { 1 } else { true }"));
+        assert!(!passes("let y: bool = { 1 }"));
+        // Without an else branch, an if has no value
+        assert!(!passes("let y = if true { 1 }"));
+        assert!(!passes("let y = { let a = 2 }"));
+    }
+
+    #[test]
+    fn loop_control () {
+        assert!(passes("while true { break }"));
+        assert!(passes("let mut i = 0; while i < 3 { i += 1; if i == 1 { continue } }"));
+        assert!(!passes("while 1 { break }"));
+        assert!(!passes("break"));
+        assert!(!passes("if true { continue }"));
+        // A function body is not within the loop around its definition
+        assert!(!passes("func f () -> void { break }"));
+        assert!(!passes("while true { break }; continue"));
+    }
+
+    #[test]
+    fn for_loops () {
+        assert!(passes("for i in 0..10 step 2 { print64(i) }"));
+        assert!(!passes("for i in 0..2.5 { }"));
+        assert!(!passes("for i in 0..10 step true { }"));
+        // The loop variable is an i64, scoped to the body
+        assert!(!passes("for i in 0..3 { let b: bool = i }"));
+        assert!(!passes("for i in 0..3 { }; print64(i)"));
+    }
+
+    #[test]
+    fn else_if_chains () {
+        assert!(passes("let n = 2; if n < 0 { print64(0) } else if n == 0 { print64(1) } else { print64(2) }"));
+        assert!(passes("let n = 2\nif n < 0 {\n}\nelse if n > 0 {\n}"));
+        assert!(!passes("if true { } else if 1 { }"));
+    }
+
+    #[test]
+    fn assignment () {
+        assert!(passes("let mut x = 1; x = 2; x += 3; x -= 1; x *= 2; x /= 2"));
+        assert!(!passes("let x = 1; x = 2"));
+        assert!(!passes("let x = 1; x += 2"));
+        assert!(!passes("let mut x = 1; x = true"));
+        assert!(!passes("let mut x = 1; x += 1.5"));
+    }
+
+    #[test]
+    fn element_assignment () {
+        assert!(passes("let mut a = [[1, 2]]; a[0][1] = 3"));
+        assert!(!passes("let a = [1, 2]; a[0] = 3"));
+        assert!(!passes("let a = [[1, 2]]; a[0][1] = 3"));
+        assert!(!passes("func f (a: [i64]) -> void { a[0] = 3 }"));
+    }
+
+    #[test]
+    fn literal_coercion () {
+        assert!(passes("let a: u8 = 5; let mut b: i32 = 0; b = 7; let mut c: [u8] = [1, 2]; c[0] = 3"));
+        assert!(passes("func f () -> u8 { return 5 }"));
+        assert!(!passes("func f () -> u8 { return 300 }"));
+        assert!(passes("func f () -> list<i64> { return list() }"));
+        assert!(passes("let b: list<u8> = list(); push(b, 5)"));
+        assert!(!passes("let b: list<u8> = list(); push(b, 300)"));
+        assert!(passes("let b: list<list<u8>> = list(); push(b, list())"));
+        // Nothing to infer 'T' from
+        assert!(!passes("print64(len(list()))"));
+    }
+
+    #[test]
+    fn casts () {
+        assert!(passes("let a = u8(300) as f64 as i64 as bool; let s = str(\"x\")"));
+        assert!(!passes("let a = bool(1.5)"));
+        assert!(!passes("void(print64(1))"));
+        assert!(!passes("print64(1) as void"));
+        assert!(!passes("let a = i64(print64(1))"));
+    }
+
+    #[test]
+    fn comparisons () {
+        assert!(passes("let a = 1 < 2; let b = 1.5 >= 2.0; let c = \"a\" <= \"b\"; let d: bool = a && b || c"));
+        assert!(!passes("let a = 1 < true"));
+        assert!(!passes("let a = true > false"));
+        assert!(!passes("let a = 1 < 2 < 3"));
+    }
+
+    #[test]
+    fn concatenation () {
+        assert!(passes("let s = \"a\" + \"b\" + str(\"c\"); println(s)"));
+        assert!(!passes("let s = \"a\" + 1"));
+        assert!(!passes("let s = 1 + \"a\""));
+        assert!(!passes("let s = \"a\" - \"b\""));
+    }
+
+    #[test]
+    fn equality () {
+        assert!(passes("let a = 1 == 2; let b = \"x\" != \"y\""));
+        assert!(!passes("let a = [1] == [1]"));
+        assert!(!passes("let a: list<i64> = list(); let b = a == a"));
+        assert!(!passes("func f () -> void { return }; let b = f() == f()"));
+    }
+}
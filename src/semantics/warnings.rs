@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+
+use super::*;
+
+/// Diagnostic that does not fail the compilation.
+pub struct Warning {
+    pub msg: String,
+    pub start: usize
+}
+
+/// Identifier usage across the program
+#[derive(Default)]
+struct Usage {
+    /// Declared variables & parameters, with what they are & where
+    declared: Vec<(Symbol, &'static str, usize)>,
+    /// Declaration IDs of variables that are read
+    read: HashSet<usize>,
+    /// Defined functions & where
+    funcs: Vec<(String, usize)>,
+    /// Functions invoked from outside of their own body
+    invoked: HashSet<String>,
+    /// Function whose body is being traversed
+    func: Option<String>,
+}
+
+/// Lints a scope resolved & type checked program. Finds unreachable code & unused identifiers.
+/// Identifiers starting with '_' are never reported as unused.
+pub fn lint (nodes: &[LNode]) -> Vec<Warning> {
+    let mut warnings = vec![];
+    let mut usage = Usage::default();
+    for node in nodes {
+        node.find_unreachable(&mut warnings);
+        node.collect_usage(&mut usage);
+    }
+
+    let exempt = |name: &str| name.starts_with('_');
+    for (sym, kind, start) in usage.declared {
+        if !usage.read.contains(&sym.id) && !exempt(&sym.name) {
+            warnings.push(Warning { msg: format!("Unused {kind} '{}'", sym.name), start });
+        }
+    }
+    for (ident, start) in usage.funcs {
+        if !usage.invoked.contains(&ident) && !exempt(&ident) {
+            warnings.push(Warning { msg: format!("Unused function '{ident}'"), start });
+        }
+    }
+
+    warnings.sort_by_key(|w| w.start);
+    warnings
+}
+
+impl LNode {
+    /// Warns on the first statement of a block following one that never falls through.
+    fn find_unreachable (&self, warnings: &mut Vec<Warning>) {
+        if let NodeData::Block { v } = &self.data {
+            let diverges = |stmt: &LElem| stmt.returns() 
+                || matches!(stmt, LElem::Node(LNode { data: NodeData::Break | NodeData::Continue, .. }));
+            if let Some(i) = v.iter().position(|stmt| diverges(stmt)) {
+                if let Some(stmt) = v.get(i+1) {
+                    warnings.push(Warning { msg: "Unreachable code".to_owned(), start: stmt.start() });
+                }
+            }
+        }
+        for child in self.get_children() {
+            if let LElem::Node(n) = child.as_ref() {
+                n.find_unreachable(warnings);
+            }
+        }
+    }
+
+    fn collect_usage (&self, usage: &mut Usage) {
+        match &self.data {
+            NodeData::Declaration { ident, .. } |
+            NodeData::For { ident, .. } => usage.declared.push((ident.clone(), "variable", self.start)),
+            NodeData::Params { v, starts } => {
+                for ((ident, _), start) in v.iter().zip(starts) {
+                    usage.declared.push((ident.clone(), "parameter", *start));
+                }
+            },
+            NodeData::Function { ident, .. } => {
+                usage.funcs.push((ident.clone(), self.start));
+                usage.func = Some(ident.clone());
+            },
+            // Recursion alone does not make a function used
+            NodeData::Invoke { ident, .. } if usage.func.as_ref() != Some(ident) => { 
+                usage.invoked.insert(ident.clone()); 
+            },
+            _ => ()
+        }
+        for child in self.get_children() {
+            match child.as_ref() {
+                LElem::Node(n)  => n.collect_usage(usage),
+                LElem::Token(t) => if let Some(sym) = t.symbol() { usage.read.insert(sym.id); },
+            }
+        }
+        if let NodeData::Function { .. } = self.data {
+            usage.func = None;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    /// Lints the source.
+    fn warnings_of (source: &str) -> Vec<super::Warning> {
+        let nodes = super::super::analyze(source).ok().expect("semantic analysis failed");
+        super::lint(&nodes)
+    }
+
+    /// Lints the source. Returns the warning messages.
+    fn lint (source: &str) -> Vec<String> {
+        warnings_of(source).into_iter().map(|w| w.msg).collect()
+    }
+
+    #[test]
+    fn warnings () {
+        assert_eq!(lint("let x = 1 print64(x)"), Vec::<String>::new());
+        assert_eq!(lint("let mut x = 1 x = 2"), vec!["Unused variable 'x'"]);
+        assert_eq!(lint("let _x = 1"), Vec::<String>::new());
+        assert_eq!(
+            lint("func f (n: i64) -> i64 { return 1 print64(2) }"), 
+            vec!["Unused function 'f'", "Unused parameter 'n'", "Unreachable code"]
+        );
+        assert_eq!(lint("func f () -> void {\n return\n print64(1)\n}\nf()"), vec!["Unreachable code"]);
+        // Recursion is not a use
+        assert_eq!(lint("func f (n: i64) -> i64 { return f(n) }"), vec!["Unused function 'f'"]);
+        // Parameters are reported at their own identifier
+        let source = "func f (a: i64, b: i64) -> i64 { return a }; f(1, 2)";
+        let w = warnings_of(source);
+        assert_eq!(w.len(), 1);
+        assert_eq!(&source[w[0].start..w[0].start+1], "b");
+    }
+}
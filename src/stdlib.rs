@@ -1,11 +1,13 @@
 use crate::lexer::types;
 
 use super::*;
-use super::semantics::TypeTable;
+use super::semantics::{TypeTable, FuncSig};
+use std::collections::HashMap;
 
 type Span<'a> = nom_locate::LocatedSpan<&'a str>;
 
-pub const RESERVED_MEM: usize = 40;
+/// Printer scratch space & stdlib messages. String literals are placed past it.
+pub const RESERVED_MEM: usize = 96;
 const PREFIX: &'static str = ";;@signature ";
 
 impl TypeTable {
@@ -13,50 +15,14 @@ impl TypeTable {
     /// Annotation Format:
     /// - Memory Reservation: ';;@reserve [bytes]'
     /// - Function Siganture: ';;@signature $[identifier] : [return-type] ([param0],[param1],..)
+    ///   Generic over 'T', e.g. ';;@signature $len : i64 ([T])'. Repeated identifiers are overloads
     
     pub fn with_std () -> Self {
-        let mut table = Self::default();
-
-        // For each line
-        for (linenum, line) in get_lib().lines().enumerate() {
-
-            let suffix = format!(" found on annotation at line {}", linenum);
-
-            // Check for prefix
-            let line = line.trim();
-            if !line.starts_with(PREFIX) { continue }
-
-            // Extract segments
-            let line = &line[PREFIX.len()..];
-            let a = if let Some(a) = line.find('$') { a } 
-                else { panic!("No identifier symbol ($) {suffix}") };
-            let b = if let Some(b) = line.find(':') { b }
-                else { panic!("No return type symbol (:) {suffix}") };
-            let c = if let Some(c) = line.find('(') { c }
-                else { panic!("No param list start symbol ('(') {suffix}") };
-            let d = if let Some(d) = line.find(')') { d }
-                else { panic!("No param list end symbol (')') {suffix}") };
-
-            // Extract values
-            let ident  = line[a+1..b].trim();
-            let r_type = if let Ok(t) = to_type(line[b+1..c].trim()) { t } 
-                else { panic!("Return type could not be interpreted: '{}'. {suffix}", &line[b+1..c]) };
-
-            let params = line[c+1..d].split(',').map(|param| {
-                let r = to_type(param.trim());
-                if r.is_err() { 
-                    panic!("Param type annotation could not be interpreted: '{}'. {suffix}", param);
-                }
-                r.unwrap()
-            }).collect::<Vec<_>>();
-
-            // Set signature
-            if table.funcs.contains_key(&ident.to_owned()) {
-                panic!("Overlapping function identifier '{}' {suffix}", ident);
-            } 
-            table.funcs.insert(ident.to_owned(), (params, r_type));
-        }
-        
+        let table = Self {
+            funcs: signatures(),
+            ..Self::default()
+        };
+
         // DEBUG: Print
         println!("=== stdlib Signatures ===");
         for (k, t) in table.funcs.iter() {
@@ -67,7 +33,66 @@ impl TypeTable {
     }
 }
 
+/// Parses the signatures annotated in the STD lib.
+pub fn signatures () -> HashMap<String, Vec<FuncSig>> {
+    let mut funcs = HashMap::new();
+
+    // For each line
+    for (linenum, line) in get_lib().lines().enumerate() {
+
+        let suffix = format!(" found on annotation at line {}", linenum);
+
+        // Check for prefix
+        let line = line.trim();
+        if !line.starts_with(PREFIX) { continue }
+
+        // Extract segments
+        let line = &line[PREFIX.len()..];
+        let a = if let Some(a) = line.find('$') { a } 
+            else { panic!("No identifier symbol ($) {suffix}") };
+        let b = if let Some(b) = line.find(':') { b }
+            else { panic!("No return type symbol (:) {suffix}") };
+        let c = if let Some(c) = line.find('(') { c }
+            else { panic!("No param list start symbol ('(') {suffix}") };
+        let d = if let Some(d) = line.find(')') { d }
+            else { panic!("No param list end symbol (')') {suffix}") };
+
+        // Extract values
+        let ident  = line[a+1..b].trim();
+        let r_type = if let Ok(t) = to_type(line[b+1..c].trim()) { t } 
+            else { panic!("Return type could not be interpreted: '{}'. {suffix}", &line[b+1..c]) };
+
+        let params = line[c+1..d].split(',').filter(|param| !param.trim().is_empty()).map(|param| {
+            let r = to_type(param.trim());
+            if r.is_err() { 
+                panic!("Param type annotation could not be interpreted: '{}'. {suffix}", param);
+            }
+            r.unwrap()
+        }).collect::<Vec<_>>();
+
+        // Set signature. Repeated identifiers are overloads
+        let sigs: &mut Vec<FuncSig> = funcs.entry(ident.to_owned()).or_default();
+        if sigs.iter().any(|(p, _)| *p == params) {
+            panic!("Overlapping signature for function identifier '{}' {suffix}", ident);
+        } 
+        sigs.push((params, r_type));
+    }
+
+    funcs
+}
+
 fn to_type (s: &str) -> Result<Type, ()> {
+    // Generic signatures use the type variable 'T'. Not part of the language itself
+    if s == "T" {
+        return Ok(Type::Generic)
+    }
+    if let Some(inner) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        return Ok(Type::Array(Box::new(to_type(inner.trim())?)))
+    }
+    if let Some(inner) = s.strip_prefix("list<").and_then(|s| s.strip_suffix('>')) {
+        return Ok(Type::List(Box::new(to_type(inner.trim())?)))
+    }
+
     let span = Span::new(s);
     if let TokenData::TYPE(t) = types(span).map_err(|_| ())?.1.data {
         Ok(t)
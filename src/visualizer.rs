@@ -63,8 +63,29 @@ impl fmt::Display for LNode {
 }
 
 impl fmt::Display for Type {
+    /// Writes the type as it is spelled in source.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self)
+        let s = match self {
+            Type::Void   => "void",
+            Type::Int    => "i64",
+            Type::I32    => "i32",
+            Type::U8     => "u8",
+            Type::U64    => "u64",
+            Type::Float  => "f64",
+            Type::Bool   => "bool",
+            Type::String => "str",
+            Type::Generic => "T",
+            Type::Array(t) => return write!(f, "[{t}]"),
+            Type::List(t)  => return write!(f, "list<{t}>"),
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl fmt::Display for Symbol {
+    /// Writes the identifier with its declaration ID. Unique within the program.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.name, self.id)
     }
 }
 
@@ -75,51 +96,70 @@ impl CilantroErrorTrait for TypeError {
         write!(f, "=== Type Error ===\n")?;
         
         write!(f, "Error at: {}\n", self.start)?;
-        {
-            // Get start & end of line slice
-            let mut a = self.start;
-            for _ in 0..20 {
-                if source.as_bytes()[a].is_ascii_control() { 
-                    a += 1;
-                    break 
-                }
-                a -= 1;
-                if a == 0 { break }
-            }
-            let mut b = self.start;
-            for _ in 0..20 {
-                if b == source.len() || source.as_bytes()[b].is_ascii_control() { 
-                    break
-                }
-                b += 1;
-            }
+        fmt_snippet(&mut f, source, self.start, &self.msg)?;
 
-            write!(f, "    ")?;
-            for c in source[a..b].chars() {
-                assert!(!c.is_ascii_control());
-                let c = c.escape_debug();
-                write!(f, "{}", c)?;
-            }
+        // Note
+        if let Some(expected) = &self.expected { write!(f, "  expected type: {}\n", expected)?; }
+        if let Some(found)    = &self.found    { write!(f, "  found type: {}\n", found)?; }
 
-            // Underline
-            write!(f, "\n    {:w$}^", "", w=self.start-a)?;
-            write!(f, "{:-<w$}{}\n", "", self.msg, w=5)?;
+        Ok(f)
+    }
+}
 
-            // Note
-            if let Some(expected) = &self.expected { write!(f, "  expected type: {}\n", expected)?; }
-            if let Some(found)    = &self.found    { write!(f, "  found type: {}\n", found)?; }
+/// Writes the source line around `start`, underlining `start` with `msg`.
+fn fmt_snippet (f: &mut String, source: &str, start: usize, msg: &str) -> fmt::Result {
+    // Get start & end of line slice
+    let mut a = start;
+    for _ in 0..20 {
+        if a == 0 || source.as_bytes()[a-1].is_ascii_control() { 
+            break 
         }
+        a -= 1;
+    }
+    let mut b = start;
+    for _ in 0..20 {
+        if b == source.len() || source.as_bytes()[b].is_ascii_control() { 
+            break
+        }
+        b += 1;
+    }
 
-        Ok(f)
+    write!(f, "    ")?;
+    for c in source[a..b].chars() {
+        assert!(!c.is_ascii_control());
+        let c = c.escape_debug();
+        write!(f, "{}", c)?;
     }
+
+    // Underline
+    write!(f, "\n    {:w$}^", "", w=start-a)?;
+    write!(f, "{:-<w$}{}\n", "", msg, w=5)?;
+
+    Ok(())
 }
 
 use super::semantics::ScopeError;
 impl CilantroErrorTrait for ScopeError {
     fn fmt (&self, source: &String) -> Result<String, fmt::Error> {
         let mut f = String::new();
-        write!(f, "== Scope Error ==")?;
-        // TODO:
+        writeln!(f, "=== Scope Error ===")?;
+
+        writeln!(f, "Error at: {}", self.start)?;
+        fmt_snippet(&mut f, source, self.start, &self.msg)?;
+
+        Ok(f)
+    }
+}
+
+use super::semantics::Warning;
+impl CilantroErrorTrait for Warning {
+    fn fmt (&self, source: &String) -> Result<String, fmt::Error> {
+        let mut f = String::new();
+        writeln!(f, "=== Warning ===")?;
+
+        writeln!(f, "Warning at: {}", self.start)?;
+        fmt_snippet(&mut f, source, self.start, &self.msg)?;
+
         Ok(f)
     }
 }
@@ -208,9 +248,10 @@ impl LNode {
         Ok(())
     }
 
-    fn get_children (&self) -> Vec<&Box<LElem>> {
+    pub fn get_children (&self) -> Vec<&Box<LElem>> {
         match &self.data {
             NodeData::Declaration { expr, .. } => vec![expr],
+            NodeData::Assignment { expr, .. } => vec![expr],
             NodeData::Function { params, block, .. } => 
                 if let Some(params) = params {
                     vec![params, block]
@@ -221,8 +262,24 @@ impl LNode {
             NodeData::Invoke { args, .. } => args.iter().map(|x| x).collect(), 
             NodeData::Expr { t1, t2, .. } => vec![t1, t2],
             NodeData::UExpr { t, .. } => vec![t],
-            NodeData::Return { expr } => vec![expr],
-            NodeData::If { expr, block } => vec![expr, block],
+            NodeData::Cast { expr, .. } => vec![expr],
+            NodeData::Array { v } => v.iter().collect(),
+            NodeData::Index { expr, index } => vec![expr, index],
+            NodeData::IndexAssignment { expr, index, value } => vec![expr, index, value],
+            NodeData::Return { expr } => expr.iter().collect(),
+            NodeData::If { expr, block, else_block } => 
+                if let Some(else_block) = else_block {
+                    vec![expr, block, else_block]
+                } else {
+                    vec![expr, block]
+                },
+            NodeData::While { expr, block } => vec![expr, block],
+            NodeData::For { from, to, step, block, .. } => 
+                if let Some(step) = step {
+                    vec![from, to, step, block]
+                } else {
+                    vec![from, to, block]
+                },
             _ => vec![],
         }
     }
@@ -237,17 +294,35 @@ impl LNode {
                 write!(f, "ident: {:?}, ", ident)?;
                 write!(f, "r_type: {}, ", r_type)?;
             },
-            NodeData::Declaration { ident, .. } => 
-                write!(f, "ident: {:?}, ", ident)?,
+            NodeData::Declaration { ident, mutable, t, .. } => {
+                write!(f, "ident: {}, ", ident)?;
+                write!(f, "mutable: {}, ", mutable)?;
+                if let Some(t) = t {
+                    write!(f, "t: {}, ", t)?;
+                }
+            },
+            NodeData::Assignment { ident, .. } |
+            NodeData::For { ident, .. } => 
+                write!(f, "ident: {}, ", ident)?,
             NodeData::Expr { op, .. } => 
                 write!(f, "op: {:?}, ", op)?,
             NodeData::UExpr { op, .. } => 
                 write!(f, "op: {:?}, ", op)?,
+            NodeData::Cast { t, .. } => 
+                write!(f, "t: {}, ", t)?,
             NodeData::Invoke { ident, .. } => 
                 write!(f, "ident: {:?}, ", ident)?,
-            NodeData::Params { v } => 
-                write!(f, "{:?}, ", v)?,
-            NodeData::If{ .. } =>
+            NodeData::Params { v, .. } => 
+                for (ident, t) in v {
+                    write!(f, "{ident}: {t}, ")?;
+                },
+            NodeData::If{ .. } |
+            NodeData::While{ .. } |
+            NodeData::Array{ .. } |
+            NodeData::Index{ .. } |
+            NodeData::IndexAssignment{ .. } |
+            NodeData::Break |
+            NodeData::Continue =>
                 write!(f, "_")?,
             _ => write!(f, "no impl")? 
         };
@@ -281,7 +356,7 @@ pub fn print_tokens (toks: &Tokens, source: &String) -> Result<String, std::fmt:
             if i - line_start > WRAP { break }
             let mut s = format!("{}", tok.data);
             if tok_i != toks.len() - 1 {
-                let d = K * (toks[tok_i+1].start - tok.start - 1);
+                let d = K * (toks[tok_i+1].start.saturating_sub(tok.start + 1));
                 s = format!("{:<width$.width$}", s, width=d);
             }
             i += s.len();